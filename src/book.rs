@@ -0,0 +1,430 @@
+use std::collections::BTreeMap;
+
+use miden_client::{accounts::AccountId, notes::NoteId};
+
+use crate::{errors::OrderError, order::Order, price::Price};
+
+// Order book
+// ================================================================================================
+
+/// Side of a market an order rests on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Orders offering the quote asset for the base asset.
+    Bid,
+    /// Orders offering the base asset for the quote asset.
+    Ask,
+}
+
+/// Orders resting at the same price, with their total size in the base asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    /// Amount of the quote asset per unit of the base asset.
+    pub price: Price,
+    pub size: u64,
+    pub orders: usize,
+}
+
+/// Notes added and removed by a sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDelta {
+    pub added: Vec<Order>,
+    pub removed: Vec<NoteId>,
+}
+
+/// Position of an order in its side: its price in quote per base, then its note id so orders at
+/// the same price do not depend on the order in which notes were loaded.
+type OrderKey = (Price, String);
+
+/// Resting orders of a market between a base and a quote asset, as known by the client at a
+/// block.
+///
+/// Each side is kept sorted by price in quote per base, so the best bid is the highest and the
+/// best ask the lowest. Orders are indexed by note id, so inserting and removing one takes
+/// logarithmic time. Expired orders are left out, since they can only be reclaimed by their
+/// creator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBook {
+    base_faucet_id: AccountId,
+    quote_faucet_id: AccountId,
+    block_num: u32,
+    bids: BTreeMap<OrderKey, Order>,
+    asks: BTreeMap<OrderKey, Order>,
+    index: BTreeMap<String, (Side, Price)>,
+}
+
+impl OrderBook {
+    /// Builds the book of the `base_faucet_id`/`quote_faucet_id` market at `block_num`, dropping
+    /// the orders of other markets, the expired ones and the ones without a note id.
+    pub fn new(
+        base_faucet_id: AccountId,
+        quote_faucet_id: AccountId,
+        block_num: u32,
+        orders: Vec<Order>,
+    ) -> Self {
+        let mut book = Self {
+            base_faucet_id,
+            quote_faucet_id,
+            block_num,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            index: BTreeMap::new(),
+        };
+        for order in orders {
+            let _ = book.insert(order);
+        }
+        book
+    }
+
+    /// Splits `orders` into the books of their markets at `block_num`. The base asset of a market
+    /// is the one offered by its first order.
+    pub fn markets(block_num: u32, orders: Vec<Order>) -> Vec<OrderBook> {
+        let mut books: Vec<OrderBook> = Vec::new();
+        for order in orders {
+            match books.iter_mut().find(|book| book.side(&order).is_some()) {
+                Some(book) => {
+                    let _ = book.insert(order);
+                }
+                None => books.push(OrderBook::new(
+                    order.source_asset().faucet_id(),
+                    order.target_asset().faucet_id(),
+                    block_num,
+                    vec![order],
+                )),
+            }
+        }
+        books
+    }
+
+    pub fn base_faucet_id(&self) -> AccountId {
+        self.base_faucet_id
+    }
+
+    pub fn quote_faucet_id(&self) -> AccountId {
+        self.quote_faucet_id
+    }
+
+    /// Block at which the book was last updated.
+    pub fn block_num(&self) -> u32 {
+        self.block_num
+    }
+
+    /// Side `order` rests on, or `None` if it belongs to another market.
+    pub fn side(&self, order: &Order) -> Option<Side> {
+        let pair = (
+            order.source_asset().faucet_id(),
+            order.target_asset().faucet_id(),
+        );
+        if pair == (self.base_faucet_id, self.quote_faucet_id) {
+            Some(Side::Ask)
+        } else if pair == (self.quote_faucet_id, self.base_faucet_id) {
+            Some(Side::Bid)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a resting order, returning whether it was added. Orders of other markets, expired
+    /// orders and OTC orders directed to a counterparty are ignored, and an order already in the
+    /// book is replaced.
+    pub fn insert(&mut self, order: Order) -> Result<bool, OrderError> {
+        let note_id = order.id().ok_or(OrderError::MissingId)?;
+        let Some(side) = self.side(&order) else {
+            return Ok(false);
+        };
+        if order.is_expired(self.block_num) || order.counterparty().is_some() {
+            return Ok(false);
+        }
+
+        self.remove(&note_id);
+        let price = match side {
+            Side::Ask => order.price(),
+            Side::Bid => order.price().inverse(),
+        };
+        let key = note_id.to_hex();
+        self.side_mut(side).insert((price, key.clone()), order);
+        self.index.insert(key, (side, price));
+        Ok(true)
+    }
+
+    /// Removes the order of `note_id`, returning it if it was in the book.
+    pub fn remove(&mut self, note_id: &NoteId) -> Option<Order> {
+        let key = note_id.to_hex();
+        let (side, price) = self.index.remove(&key)?;
+        self.side_mut(side).remove(&(price, key))
+    }
+
+    pub fn get(&self, note_id: &NoteId) -> Option<&Order> {
+        let key = note_id.to_hex();
+        let (side, price) = self.index.get(&key)?;
+        self.side_ref(*side).get(&(*price, key))
+    }
+
+    pub fn contains(&self, note_id: &NoteId) -> bool {
+        self.index.contains_key(&note_id.to_hex())
+    }
+
+    /// Applies the notes added and removed since the last update, then drops the orders expired
+    /// at `block_num`.
+    pub fn apply(&mut self, delta: &BookDelta, block_num: u32) {
+        for note_id in &delta.removed {
+            self.remove(note_id);
+        }
+        self.expire(block_num);
+        for order in &delta.added {
+            let _ = self.insert(*order);
+        }
+    }
+
+    /// Moves the book to `block_num`, dropping the orders expired by then.
+    pub fn expire(&mut self, block_num: u32) {
+        self.block_num = block_num;
+        let expired: Vec<NoteId> = self
+            .bids
+            .values()
+            .chain(self.asks.values())
+            .filter(|order| order.is_expired(block_num))
+            .filter_map(Order::id)
+            .collect();
+        for note_id in &expired {
+            self.remove(note_id);
+        }
+    }
+
+    /// Orders of `side`, best price first.
+    pub fn orders(&self, side: Side) -> Vec<Order> {
+        match side {
+            Side::Bid => self.bids.values().rev().copied().collect(),
+            Side::Ask => self.asks.values().copied().collect(),
+        }
+    }
+
+    /// Orders offering the quote asset, highest price first.
+    pub fn bids(&self) -> Vec<Order> {
+        self.orders(Side::Bid)
+    }
+
+    /// Orders offering the base asset, lowest price first.
+    pub fn asks(&self) -> Vec<Order> {
+        self.orders(Side::Ask)
+    }
+
+    /// Orders offering `faucet_id`, the ones an order requesting it can fill, best price first.
+    pub fn offers(&self, faucet_id: AccountId) -> Vec<Order> {
+        if faucet_id == self.base_faucet_id {
+            self.asks()
+        } else if faucet_id == self.quote_faucet_id {
+            self.bids()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&Order> {
+        self.bids.values().next_back()
+    }
+
+    pub fn best_ask(&self) -> Option<&Order> {
+        self.asks.values().next()
+    }
+
+    /// Highest price, in quote per base, a bid pays for the base asset.
+    pub fn best_bid_price(&self) -> Option<Price> {
+        self.bids.keys().next_back().map(|(price, _)| *price)
+    }
+
+    /// Lowest price, in quote per base, an ask sells the base asset at.
+    pub fn best_ask_price(&self) -> Option<Price> {
+        self.asks.keys().next().map(|(price, _)| *price)
+    }
+
+    /// Lowest ask price minus highest bid price, or `None` if a side is empty or the book is
+    /// crossed.
+    pub fn spread(&self) -> Option<Price> {
+        self.best_ask_price()?.checked_sub(&self.best_bid_price()?)
+    }
+
+    /// Spread in basis points of the mid price.
+    pub fn spread_bps(&self) -> Option<i64> {
+        let mid_price = self.mid_price()?;
+        mid_price.checked_add(&self.spread()?)?.bps_from(&mid_price)
+    }
+
+    /// Price halfway between the best bid and the best ask.
+    pub fn mid_price(&self) -> Option<Price> {
+        self.best_bid_price()?.midpoint(&self.best_ask_price()?)
+    }
+
+    /// Orders of `side` aggregated by price level, best price first. With a `tick`, bid prices
+    /// are rounded down and ask prices up to a multiple of it, so levels never look better than
+    /// the orders they aggregate.
+    pub fn depth(&self, side: Side, tick: Option<Price>) -> Vec<Level> {
+        let mut levels: Vec<Level> = Vec::new();
+        let orders: Box<dyn Iterator<Item = (&OrderKey, &Order)>> = match side {
+            Side::Bid => Box::new(self.bids.iter().rev()),
+            Side::Ask => Box::new(self.asks.iter()),
+        };
+
+        for ((price, _), order) in orders {
+            let price = tick
+                .and_then(|tick| match side {
+                    Side::Bid => price.floor_to(&tick),
+                    Side::Ask => price.ceil_to(&tick),
+                })
+                .unwrap_or(*price);
+            let size = Self::base_amount(side, order);
+            match levels.last_mut() {
+                Some(level) if level.price == price => {
+                    level.size += size;
+                    level.orders += 1;
+                }
+                _ => levels.push(Level {
+                    price,
+                    size,
+                    orders: 1,
+                }),
+            }
+        }
+        levels
+    }
+
+    /// Amount of the base asset offered by an ask or requested by a bid.
+    pub fn base_amount(side: Side, order: &Order) -> u64 {
+        match side {
+            Side::Bid => order.target_amount(),
+            Side::Ask => order.source_amount(),
+        }
+    }
+
+    /// Amount of the quote asset requested by an ask or offered by a bid.
+    pub fn quote_amount(side: Side, order: &Order) -> u64 {
+        match side {
+            Side::Bid => order.source_amount(),
+            Side::Ask => order.target_amount(),
+        }
+    }
+
+    /// Total size of the orders of `side`, in the base asset.
+    pub fn liquidity(&self, side: Side) -> u64 {
+        self.side_ref(side)
+            .values()
+            .map(|order| Self::base_amount(side, order))
+            .sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn side_ref(&self, side: Side) -> &BTreeMap<OrderKey, Order> {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<OrderKey, Order> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+        notes::NoteId,
+    };
+
+    use super::{BookDelta, Level, OrderBook, Side};
+    use crate::{order::Order, price::Price};
+
+    fn note_id(index: u8) -> NoteId {
+        NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap()
+    }
+
+    #[test]
+    fn books_keep_both_sides_sorted_by_price() {
+        let base_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let quote_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let other_faucet_id = AccountId::from_hex("0x2a3c5f8e1d7b4c90").unwrap();
+        let order = |id, offered: AccountId, offered_amount, requested: AccountId, amount| {
+            Order::new(
+                Some(note_id(id)),
+                Asset::Fungible(FungibleAsset::new(offered, offered_amount).unwrap()),
+                Asset::Fungible(FungibleAsset::new(requested, amount).unwrap()),
+            )
+        };
+
+        // asks sell the base asset at 1 and 2 quote per base, bids buy it at 1/2
+        let cheap_ask = order(1, base_faucet_id, 10, quote_faucet_id, 10);
+        let same_price_ask = order(2, base_faucet_id, 5, quote_faucet_id, 5);
+        let expensive_ask = order(3, base_faucet_id, 10, quote_faucet_id, 20);
+        let bid = order(4, quote_faucet_id, 10, base_faucet_id, 20);
+        let expired_bid =
+            order(5, quote_faucet_id, 10, base_faucet_id, 10).with_expiration(Some(5));
+        let other_market = order(6, other_faucet_id, 10, quote_faucet_id, 10);
+
+        let mut book = OrderBook::new(
+            base_faucet_id,
+            quote_faucet_id,
+            10,
+            vec![expensive_ask, bid, other_market, expired_bid, cheap_ask],
+        );
+        assert_eq!(book.len(), 3);
+        assert_eq!(book.asks(), vec![cheap_ask, expensive_ask]);
+        assert_eq!(book.offers(quote_faucet_id), vec![bid]);
+        assert_eq!(book.offers(other_faucet_id), vec![]);
+        assert_eq!(book.best_bid_price(), Some(Price::new(1, 2)));
+        assert_eq!(book.best_ask_price(), Some(Price::new(1, 1)));
+        assert_eq!(book.spread(), Some(Price::new(1, 2)));
+        assert_eq!(book.mid_price(), Some(Price::new(3, 4)));
+        assert_eq!(book.spread_bps(), Some(6666));
+
+        // sync deltas add and remove orders by note id
+        let delta = BookDelta {
+            added: vec![same_price_ask],
+            removed: vec![note_id(4)],
+        };
+        book.apply(&delta, 11);
+        assert_eq!(book.best_bid(), None);
+        assert_eq!(book.get(&note_id(2)), Some(&same_price_ask));
+        assert_eq!(
+            book.depth(Side::Ask, None),
+            vec![
+                Level {
+                    price: Price::new(1, 1),
+                    size: 15,
+                    orders: 2
+                },
+                Level {
+                    price: Price::new(2, 1),
+                    size: 10,
+                    orders: 1
+                },
+            ]
+        );
+        assert_eq!(
+            book.depth(Side::Ask, Some(Price::new(5, 1))),
+            vec![Level {
+                price: Price::new(5, 1),
+                size: 25,
+                orders: 3
+            }]
+        );
+
+        assert_eq!(book.remove(&note_id(1)), Some(cheap_ask));
+        assert!(!book.contains(&note_id(1)));
+        assert_eq!(book.best_ask(), Some(&same_price_ask));
+    }
+}
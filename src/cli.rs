@@ -1,11 +1,12 @@
 use clap::Parser;
 
-use crate::{
-    commands::{
-        demo::DemoCmd, init::InitCmd, list::ListCmd, login::LoginCmd, order::OrderCmd,
-        query::QueryCmd, setup::SetupCmd, sync::SyncCmd,
-    },
-    utils::setup_client,
+use miden_order_book::utils::setup_client;
+
+use crate::commands::{
+    auction::AuctionCmd, demo::DemoCmd, export::ExportCmd, import::ImportCmd, init::InitCmd,
+    list::ListCmd, login::LoginCmd, matcher::MatcherCmd, offers::OffersCmd,
+    open_orders::OpenOrdersCmd, order::OrderCmd, query::QueryCmd, reclaim::ReclaimCmd,
+    setup::SetupCmd, shared::SharedCmd, sync::SyncCmd, ticker::TickerCmd, watch::WatchCmd,
 };
 
 /// CLI actions
@@ -18,7 +19,17 @@ pub enum Command {
     List(ListCmd),
     Sync(SyncCmd),
     Query(QueryCmd),
+    Reclaim(ReclaimCmd),
     Demo(DemoCmd),
+    Auction(AuctionCmd),
+    Matcher(MatcherCmd),
+    OpenOrders(OpenOrdersCmd),
+    Watch(WatchCmd),
+    Ticker(TickerCmd),
+    Export(ExportCmd),
+    Import(ImportCmd),
+    Shared(SharedCmd),
+    Offers(OffersCmd),
 }
 
 /// Root CLI struct
@@ -46,9 +57,19 @@ impl Cli {
             Command::Sync(sync) => sync.execute(&mut client).await,
             Command::Init(init) => init.execute(),
             Command::Query(query) => query.execute(&mut client).await,
+            Command::Reclaim(reclaim) => reclaim.execute(&mut client).await,
             Command::List(list) => list.execute(&mut client),
             Command::Login(login) => login.execute(&mut client),
             Command::Demo(demo) => demo.execute(&mut client).await,
+            Command::Auction(auction) => auction.execute(&mut client).await,
+            Command::Matcher(matcher) => matcher.execute(&mut client).await,
+            Command::OpenOrders(open_orders) => open_orders.execute(),
+            Command::Watch(watch) => watch.execute(&mut client).await,
+            Command::Ticker(ticker) => ticker.execute(&mut client).await,
+            Command::Export(export) => export.execute(&mut client),
+            Command::Import(import) => import.execute(&mut client).await,
+            Command::Shared(shared) => shared.execute(),
+            Command::Offers(offers) => offers.execute(&mut client).await,
         }
     }
 }
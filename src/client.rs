@@ -0,0 +1,704 @@
+use std::time::Duration;
+
+use miden_client::{
+    accounts::AccountId,
+    assets::Asset,
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    notes::{Note, NoteId},
+    rpc::NodeRpcClient,
+    store::{InputNoteRecord, NoteFilter, NoteStatus, Store},
+    transactions::{request::TransactionRequest, TransactionResult},
+    Client, Word,
+};
+use miden_objects::notes::{NoteDetails, NoteFile};
+
+use crate::{
+    book::{BookDelta, OrderBook, Side},
+    errors::{OrderBookError, OrderError},
+    market::{MarketSummary, Volume},
+    notes::{is_leftover_serial, leftover_note, payback_serial, swap_tags, SwapNote},
+    order::{
+        asset_amount, fill_order, prevent_self_trade, Fill, Order, Quote, SelfTradePrevention,
+        Settlement, TimeInForce,
+    },
+    safety::{is_trusted_script, VaultDelta},
+    utils::{
+        fills_transaction_request, get_notes_by_tag, is_note_consumed, is_swap_note,
+        orders_from_notes, settlement_transaction_request,
+    },
+};
+
+/// Outcome of an order placed with [OrderBookClient::place].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    /// Resting orders filled by the order.
+    pub fills: Vec<Fill>,
+    /// Part of the order added to the order book.
+    pub posted: Option<Order>,
+}
+
+impl Placement {
+    /// Whether nothing was filled nor posted, e.g. for a fill-or-kill order that could not be
+    /// filled entirely.
+    pub fn is_empty(&self) -> bool {
+        self.fills.is_empty() && self.posted.is_none()
+    }
+}
+
+/// Outcome of the orders cancelled with [OrderBookClient::cancel].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cancellation {
+    /// Orders consumed back into the wallet of their creator.
+    pub cancelled: Vec<Order>,
+    /// Notes already consumed by a counterparty, which cannot be cancelled anymore.
+    pub consumed: Vec<NoteId>,
+}
+
+// Order book client
+// ================================================================================================
+
+/// Order book operations on top of a Miden client.
+///
+/// Orders are SWAPp notes, looked up by the swap tag of their pair among the notes of the
+/// client store, so the tags of the markets of interest must be tracked and the client synced
+/// for the books to be up to date. Orders of the account placing an order are never filled.
+pub struct OrderBookClient<'a, N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>
+{
+    client: &'a mut Client<N, R, S, A>,
+}
+
+impl<'a, N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>
+    OrderBookClient<'a, N, R, S, A>
+{
+    pub fn new(client: &'a mut Client<N, R, S, A>) -> Self {
+        Self { client }
+    }
+
+    /// Syncs the client with the node, returning the block it is synced to.
+    pub async fn sync(&mut self) -> Result<u32, OrderBookError> {
+        self.client.sync_state().await?;
+        Ok(self.client.get_sync_height()?)
+    }
+
+    /// Syncs the client with the node and applies the notes received and consumed since the last
+    /// sync to `book`, returning the changes applied.
+    pub async fn sync_book(&mut self, book: &mut OrderBook) -> Result<BookDelta, OrderBookError> {
+        let summary = self.client.sync_state().await?;
+
+        // Committed notes include the output notes of the client, which are not input notes
+        let mut delta = BookDelta {
+            removed: summary.consumed_notes.clone(),
+            ..Default::default()
+        };
+        for note_id in summary
+            .received_notes
+            .iter()
+            .chain(summary.committed_notes.iter())
+        {
+            let Ok(note) = self.client.get_input_note(*note_id) else {
+                continue;
+            };
+            if is_swap_note(&note) && !is_note_consumed(&note) {
+                delta.added.extend(orders_from_notes([note]));
+            }
+        }
+
+        book.apply(&delta, summary.block_num);
+        Ok(delta)
+    }
+
+    /// Loads the active orders of the `base_faucet_id`/`quote_faucet_id` market at the current
+    /// sync height, public orders and the private orders imported into the store alike.
+    pub fn load_book(
+        &self,
+        base_faucet_id: AccountId,
+        quote_faucet_id: AccountId,
+    ) -> Result<OrderBook, OrderBookError> {
+        let block_num = self.client.get_sync_height()?;
+
+        let mut orders = Vec::new();
+        for (offered, requested) in [
+            (base_faucet_id, quote_faucet_id),
+            (quote_faucet_id, base_faucet_id),
+        ] {
+            let tags = swap_tags(offered, requested)
+                .map_err(|e| OrderBookError::InvalidAsset(e.to_string()))?;
+            orders.extend(orders_from_notes(
+                tags.into_iter()
+                    .flat_map(|tag| get_notes_by_tag(self.client, tag))
+                    .filter(|note| !is_note_consumed(note)),
+            ));
+        }
+
+        Ok(OrderBook::new(
+            base_faucet_id,
+            quote_faucet_id,
+            block_num,
+            orders,
+        ))
+    }
+
+    /// Summarizes the `base_faucet_id`/`quote_faucet_id` market, with the volume traded since
+    /// `since_block`.
+    pub fn summary(
+        &self,
+        base_faucet_id: AccountId,
+        quote_faucet_id: AccountId,
+        since_block: u32,
+    ) -> Result<MarketSummary, OrderBookError> {
+        let book = self.load_book(base_faucet_id, quote_faucet_id)?;
+        let volume = self.volume(base_faucet_id, quote_faucet_id, since_block)?;
+        Ok(MarketSummary::new(&book, since_block, volume))
+    }
+
+    /// Volume of the `base_faucet_id`/`quote_faucet_id` market since `since_block`, from the
+    /// orders consumed by a counterparty from that block on.
+    ///
+    /// Only the notes of the client store are known, so the volume covers the fills that happened
+    /// while the tags of the market were tracked. A note consumed by its creator was cancelled
+    /// rather than filled, and the amount re-emitted by a partial fill is not counted as traded.
+    pub fn volume(
+        &self,
+        base_faucet_id: AccountId,
+        quote_faucet_id: AccountId,
+        since_block: u32,
+    ) -> Result<Volume, OrderBookError> {
+        let mut volume = Volume::default();
+        for (offered, requested, side) in [
+            (base_faucet_id, quote_faucet_id, Side::Ask),
+            (quote_faucet_id, base_faucet_id, Side::Bid),
+        ] {
+            let tags = swap_tags(offered, requested)
+                .map_err(|e| OrderBookError::InvalidAsset(e.to_string()))?;
+            let notes: Vec<_> = tags
+                .into_iter()
+                .flat_map(|tag| get_notes_by_tag(self.client, tag))
+                .filter(is_swap_note)
+                .collect();
+
+            for note in &notes {
+                let NoteStatus::Consumed {
+                    consumer_account_id,
+                    block_height,
+                } = note.status()
+                else {
+                    continue;
+                };
+                let Ok(order) = Order::try_from(note) else {
+                    continue;
+                };
+                if block_height < u64::from(since_block)
+                    || consumer_account_id.is_some_and(|id| order.creator() == Some(id))
+                {
+                    continue;
+                }
+
+                let serial_num = note.details().serial_num();
+                let leftover = notes
+                    .iter()
+                    .find(|other| is_leftover_serial(serial_num, other.details().serial_num()))
+                    .and_then(|other| Order::try_from(other).ok());
+                volume.add_fill(side, &order, leftover.as_ref());
+            }
+        }
+
+        Ok(volume)
+    }
+
+    /// Active OTC orders directed to `account_id`. Public ones are received on sync, since they
+    /// are tagged for their counterparty, private ones once imported.
+    pub fn offers(&self, account_id: AccountId) -> Result<Vec<Order>, OrderBookError> {
+        let block_num = self.client.get_sync_height()?;
+        let notes = self.client.get_input_notes(NoteFilter::All)?;
+
+        let offers = orders_from_notes(
+            notes
+                .into_iter()
+                .filter(|note| is_swap_note(note) && !is_note_consumed(note)),
+        )
+        .into_iter()
+        .filter(|order| order.counterparty() == Some(account_id) && !order.is_expired(block_num))
+        .collect();
+        Ok(offers)
+    }
+
+    /// Balance of `faucet_id` in the vault of `account_id`.
+    pub fn balance(
+        &self,
+        account_id: AccountId,
+        faucet_id: AccountId,
+    ) -> Result<u64, OrderBookError> {
+        let (account, _) = self.client.get_account(account_id)?;
+        account
+            .vault()
+            .get_balance(faucet_id)
+            .map_err(|e| OrderBookError::InvalidAsset(e.to_string()))
+    }
+
+    /// Previews `order` placed by `account_id`, searching the best fills for at most
+    /// `time_budget`. Nothing is executed.
+    pub fn quote(
+        &self,
+        account_id: AccountId,
+        order: Order,
+        time_budget: Duration,
+    ) -> Result<Quote, OrderBookError> {
+        let order = order.with_creator(account_id);
+        let existing_orders = self.orders_filling(order)?;
+        let fills = Self::fills(order, existing_orders.clone(), time_budget)?;
+
+        let source_amount = order.source_amount();
+        Ok(Quote::new(source_amount, &existing_orders, fills))
+    }
+
+    /// Executes the transaction placing `order` with the fills of `quote` locally, without
+    /// proving nor submitting it, to check that it would succeed and pass
+    /// [OrderBookClient::verify_fills].
+    pub fn simulate(
+        &mut self,
+        account_id: AccountId,
+        order: Order,
+        quote: &Quote,
+    ) -> Result<(), OrderBookError> {
+        let posted = resting_remainder(order, quote.fills());
+        if quote.fills().is_empty() && posted.is_none() {
+            return Err(OrderError::FailedFill(order).into());
+        }
+        self.check_balance(account_id, order, quote.fills(), posted)?;
+        self.verify_fills(account_id, quote.fills(), posted)?;
+
+        Ok(())
+    }
+
+    /// Places `order` on behalf of `account_id`: fills what its time in force allows and posts
+    /// the remainder of good-til-cancelled and post-only orders, in a single transaction.
+    ///
+    /// Orders that cannot be filled as required return an empty [Placement], post-only orders
+    /// matching the book fail with [OrderError::WouldCross].
+    pub async fn place(
+        &mut self,
+        account_id: AccountId,
+        order: Order,
+        time_budget: Duration,
+    ) -> Result<Placement, OrderBookError> {
+        let order = order.with_creator(account_id);
+        let existing_orders = self.orders_filling(order)?;
+        let fills = Self::fills(order, existing_orders, time_budget)?;
+        let posted = resting_remainder(order, &fills);
+
+        let placement = Placement { fills, posted };
+        if placement.is_empty() {
+            return Ok(placement);
+        }
+        self.check_balance(account_id, order, &placement.fills, posted)?;
+        self.execute_fills(account_id, &placement.fills, posted)
+            .await?;
+
+        Ok(placement)
+    }
+
+    /// Consumes the notes of `fills` in order and posts `remainder` in a single transaction, once
+    /// verified with [OrderBookClient::verify_fills].
+    pub async fn execute_fills(
+        &mut self,
+        account_id: AccountId,
+        fills: &[Fill],
+        remainder: Option<Order>,
+    ) -> Result<(), OrderBookError> {
+        let transaction = self.verify_fills(account_id, fills, remainder)?;
+        self.submit(transaction).await
+    }
+
+    /// Executes the transaction consuming the notes of `fills` and posting `remainder` locally,
+    /// returning it to be submitted if it is safe.
+    ///
+    /// Every note consumed must run a [trusted swap script](crate::safety::trusted_swap_scripts),
+    /// and the vault of `account_id` must change by exactly what the fills promise, see
+    /// [VaultDelta::of_fills]. Otherwise nothing is submitted and the error details the notes or
+    /// assets at fault.
+    pub fn verify_fills(
+        &mut self,
+        account_id: AccountId,
+        fills: &[Fill],
+        remainder: Option<Order>,
+    ) -> Result<TransactionResult, OrderBookError> {
+        self.verify_fills_reclaiming(account_id, fills, remainder, &[])
+    }
+
+    /// Same as [OrderBookClient::verify_fills], also consuming the orders of `reclaimed` back into
+    /// the wallet of their creator `account_id` within the same transaction.
+    pub fn verify_fills_reclaiming(
+        &mut self,
+        account_id: AccountId,
+        fills: &[Fill],
+        remainder: Option<Order>,
+        reclaimed: &[Order],
+    ) -> Result<TransactionResult, OrderBookError> {
+        let refused: Vec<NoteId> = reclaimed
+            .iter()
+            .filter(|order| !order.can_be_reclaimed_by(account_id))
+            .filter_map(Order::id)
+            .collect();
+        if !refused.is_empty() {
+            return Err(OrderBookError::NotCreator(refused));
+        }
+
+        let note_assets = self.trusted_note_assets(
+            fills
+                .iter()
+                .map(Fill::order)
+                .chain(reclaimed.iter().copied()),
+        )?;
+
+        let transaction_request =
+            fills_transaction_request(fills, remainder, reclaimed, account_id, self.client.rng())?;
+        let expected = VaultDelta::of_fills(fills, remainder, reclaimed, &note_assets);
+        self.execute_expecting(account_id, transaction_request, &expected)
+    }
+
+    /// Settles a batch auction from `account_id`, which must hold what the settlement requires
+    /// beforehand, see [Settlement::required]. The transaction is verified like the ones of
+    /// [OrderBookClient::verify_fills], the refunds being spent on top of the fills.
+    pub async fn settle_batch(
+        &mut self,
+        account_id: AccountId,
+        settlement: &Settlement,
+    ) -> Result<(), OrderBookError> {
+        for (faucet_id, required) in settlement.required() {
+            let available = self.balance(account_id, faucet_id)?;
+            if available < required {
+                return Err(OrderBookError::InsufficientBalance {
+                    faucet_id,
+                    required,
+                    available,
+                });
+            }
+        }
+
+        let fills = settlement.fills();
+        let note_assets = self.trusted_note_assets(fills.iter().map(Fill::order))?;
+
+        let transaction_request =
+            settlement_transaction_request(settlement, account_id, self.client.rng())?;
+        let mut expected = VaultDelta::of_fills(fills, None, &[], &note_assets);
+        for asset in settlement.refunds().iter().flat_map(|(_, assets)| assets) {
+            expected.add(*asset, -i128::from(asset_amount(asset)));
+        }
+        let transaction = self.execute_expecting(account_id, transaction_request, &expected)?;
+        self.submit(transaction).await
+    }
+
+    /// Assets carried by the notes of `orders`, refusing notes that do not run a trusted swap
+    /// script.
+    fn trusted_note_assets(
+        &self,
+        orders: impl Iterator<Item = Order>,
+    ) -> Result<Vec<(NoteId, Vec<Asset>)>, OrderBookError> {
+        let mut untrusted = Vec::new();
+        let mut note_assets = Vec::new();
+        for order in orders {
+            let note_id = order.id().ok_or(OrderError::MissingId)?;
+            let note = self
+                .client
+                .get_input_note(note_id)
+                .map_err(|_| OrderBookError::UnknownNote(note_id))?;
+            let script_root = note.details().script().hash();
+            if !is_trusted_script(script_root) {
+                untrusted.push((note_id, script_root));
+            }
+            note_assets.push((note_id, note.assets().iter().copied().collect()));
+        }
+        if !untrusted.is_empty() {
+            return Err(OrderBookError::UntrustedScripts(untrusted));
+        }
+
+        Ok(note_assets)
+    }
+
+    /// Executes `transaction_request` locally, refusing it unless the vault of `account_id`
+    /// changes by exactly `expected`.
+    fn execute_expecting(
+        &mut self,
+        account_id: AccountId,
+        transaction_request: TransactionRequest,
+        expected: &VaultDelta,
+    ) -> Result<TransactionResult, OrderBookError> {
+        let transaction = self
+            .client
+            .new_transaction(account_id, transaction_request)?;
+
+        let actual = VaultDelta::from(transaction.account_delta().vault());
+        let mismatches = expected.mismatches(&actual);
+        if !mismatches.is_empty() {
+            return Err(OrderBookError::UnexpectedVaultDelta(mismatches));
+        }
+
+        Ok(transaction)
+    }
+
+    /// Proves and submits a transaction executed locally.
+    pub async fn submit(&mut self, transaction: TransactionResult) -> Result<(), OrderBookError> {
+        self.client.submit_transaction(transaction).await?;
+        Ok(())
+    }
+
+    /// Exports the note of `note_id`, created by the client, to be shared off-chain. The note is
+    /// exported with its inclusion proof once committed, and with its details and tag before.
+    ///
+    /// Private orders are only known to their creator until their note is shared, see
+    /// [OrderBookClient::import_note].
+    pub fn export_note(&self, note_id: NoteId) -> Result<NoteFile, OrderBookError> {
+        let note = self
+            .client
+            .get_output_note(note_id)
+            .map_err(|_| OrderBookError::UnknownNote(note_id))?;
+        let tag = note.metadata().tag();
+
+        let note_file = match note.inclusion_proof().cloned() {
+            Some(proof) => {
+                let note = Note::try_from(note)
+                    .map_err(|e| OrderBookError::InvalidNoteFile(e.to_string()))?;
+                NoteFile::NoteWithProof(note, proof)
+            }
+            None => NoteFile::NoteDetails {
+                details: NoteDetails::try_from(note)
+                    .map_err(|e| OrderBookError::InvalidNoteFile(e.to_string()))?,
+                after_block_num: self.client.get_sync_height()?,
+                tag: Some(tag),
+            },
+        };
+        Ok(note_file)
+    }
+
+    /// Imports a note shared off-chain and tracks its tag, returning the order it rests.
+    ///
+    /// Once imported, private orders are listed and filled like public ones. Notes that are not
+    /// valid swap notes are refused before being stored, as they would be skipped by the books.
+    pub async fn import_note(&mut self, note_file: NoteFile) -> Result<Order, OrderBookError> {
+        let (note_id, tag, swap_note) = match &note_file {
+            NoteFile::NoteWithProof(note, _) => {
+                (note.id(), note.metadata().tag(), SwapNote::try_from(note))
+            }
+            NoteFile::NoteDetails {
+                details,
+                tag: Some(tag),
+                ..
+            } => (details.id(), *tag, SwapNote::try_from((details, *tag))),
+            _ => {
+                return Err(OrderBookError::InvalidNoteFile(
+                    "the file carries neither the note nor its tag".to_string(),
+                ))
+            }
+        };
+        let order = swap_note
+            .and_then(|swap_note| Order::try_from(&swap_note))
+            .map_err(|err| OrderBookError::InvalidNote(note_id, err))?;
+
+        // SWAPp notes are checked against the tag in their inputs, SWAP notes against their pair
+        let pair_tags = swap_tags(
+            order.source_asset().faucet_id(),
+            order.target_asset().faucet_id(),
+        )
+        .map_err(|e| OrderBookError::InvalidNoteFile(e.to_string()))?;
+        if !order.is_partial() && !pair_tags.contains(&tag) {
+            return Err(OrderBookError::InvalidNoteFile(format!(
+                "tag {} is not a tag of the pair of the note",
+                tag.inner()
+            )));
+        }
+
+        self.client.import_note(note_file).await?;
+        self.client.add_note_tag(tag)?;
+        Ok(order)
+    }
+
+    /// Cancels the orders of `note_ids` created by `account_id`, consuming them back into its
+    /// wallet in a single transaction. Notes already consumed are reported rather than cancelled.
+    ///
+    /// Orders directed to a counterparty are tagged for it, so their creator only knows them as
+    /// output notes. They are cancelled from these, after following their partial fills through
+    /// the notes paying the creator back.
+    pub async fn cancel(
+        &mut self,
+        account_id: AccountId,
+        note_ids: &[NoteId],
+    ) -> Result<Cancellation, OrderBookError> {
+        let mut cancelled = Vec::new();
+        let mut consumed = Vec::new();
+        let mut output_notes = Vec::new();
+        for note_id in note_ids {
+            let note = match self.client.get_input_note(*note_id) {
+                Ok(note) => note,
+                Err(_) => match self.own_order_note(*note_id)? {
+                    Some(note) => {
+                        output_notes.push(note.clone());
+                        InputNoteRecord::from(note)
+                    }
+                    None => {
+                        consumed.push(*note_id);
+                        continue;
+                    }
+                },
+            };
+            if is_note_consumed(&note) {
+                consumed.push(*note_id);
+            } else {
+                let order = Order::try_from(&note)
+                    .map_err(|err| OrderBookError::InvalidNote(*note_id, err))?;
+                cancelled.push(order);
+            }
+        }
+
+        // Only SWAPp notes can be consumed back, and only by their creator
+        let refused: Vec<NoteId> = cancelled
+            .iter()
+            .filter(|order| !order.can_be_reclaimed_by(account_id))
+            .filter_map(Order::id)
+            .collect();
+        if !refused.is_empty() {
+            return Err(OrderBookError::NotCreator(refused));
+        }
+
+        if !cancelled.is_empty() {
+            let input_notes = cancelled
+                .iter()
+                .filter_map(Order::id)
+                .filter(|id| output_notes.iter().all(|note| note.id() != *id))
+                .map(|id| (id, None))
+                .collect::<Vec<(NoteId, Option<Word>)>>();
+            let transaction_request = TransactionRequest::new()
+                .with_authenticated_input_notes(input_notes)
+                .with_unauthenticated_input_notes(
+                    output_notes.into_iter().map(|note| (note, None)),
+                );
+            let transaction = self
+                .client
+                .new_transaction(account_id, transaction_request)?;
+            self.client.submit_transaction(transaction).await?;
+        }
+
+        Ok(Cancellation {
+            cancelled,
+            consumed,
+        })
+    }
+
+    /// Consumes resting orders back into the wallet of their creator `account_id` in a single
+    /// transaction.
+    ///
+    /// The SWAPp note script returns the offered asset when the note is consumed by its creator,
+    /// so no note arguments are needed.
+    pub async fn reclaim(
+        &mut self,
+        account_id: AccountId,
+        orders: &[Order],
+    ) -> Result<(), OrderBookError> {
+        let input_notes = orders
+            .iter()
+            .map(|order| order.id().map(|id| (id, None)).ok_or(OrderError::MissingId))
+            .collect::<Result<Vec<(NoteId, Option<Word>)>, OrderError>>()?;
+
+        let transaction_request =
+            TransactionRequest::new().with_authenticated_input_notes(input_notes);
+        let transaction = self
+            .client
+            .new_transaction(account_id, transaction_request)?;
+        self.client.submit_transaction(transaction).await?;
+
+        Ok(())
+    }
+
+    /// Current note of the own order first posted in the output note of `note_id`, none once it
+    /// has been filled entirely.
+    ///
+    /// Partial fills are followed through the notes paying the creator back, whose serial number
+    /// is derived from the filled note, and the leftover note is rebuilt as the SWAPp script does.
+    fn own_order_note(&self, note_id: NoteId) -> Result<Option<Note>, OrderBookError> {
+        let note = self
+            .client
+            .get_output_note(note_id)
+            .map_err(|_| OrderBookError::UnknownNote(note_id))?;
+        let mut note = Note::try_from(note).map_err(|_| OrderBookError::UnknownNote(note_id))?;
+
+        let received_notes = self.client.get_input_notes(NoteFilter::All)?;
+        loop {
+            let payback_serial_num = payback_serial(note.serial_num());
+            let Some(payback) = received_notes
+                .iter()
+                .find(|payback| payback.details().serial_num() == payback_serial_num)
+            else {
+                return Ok(Some(note));
+            };
+            let (Some(metadata), Some(Asset::Fungible(paid))) =
+                (payback.metadata(), payback.assets().iter().next())
+            else {
+                return Ok(Some(note));
+            };
+
+            match leftover_note(&note, paid.amount(), metadata.sender())? {
+                Some(leftover) => note = leftover,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Orders of the book that `order` can fill, leaving out the ones of its creator.
+    fn orders_filling(&self, order: Order) -> Result<Vec<Order>, OrderBookError> {
+        let source_faucet_id = order.source_asset().faucet_id();
+        let target_faucet_id = order.target_asset().faucet_id();
+        let book = self.load_book(target_faucet_id, source_faucet_id)?;
+
+        let (orders, _) = prevent_self_trade(
+            order,
+            book.offers(target_faucet_id),
+            SelfTradePrevention::Skip,
+        )?;
+        Ok(orders)
+    }
+
+    /// Fills of `order`, none if it cannot be filled as its time in force requires.
+    fn fills(
+        order: Order,
+        existing_orders: Vec<Order>,
+        time_budget: Duration,
+    ) -> Result<Vec<Fill>, OrderBookError> {
+        match fill_order(order, existing_orders, time_budget) {
+            Ok(fills) => Ok(fills),
+            Err(OrderError::FailedFill(_)) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Checks that `account_id` can pay for `fills` and post `posted`.
+    fn check_balance(
+        &self,
+        account_id: AccountId,
+        order: Order,
+        fills: &[Fill],
+        posted: Option<Order>,
+    ) -> Result<(), OrderBookError> {
+        let faucet_id = order.source_asset().faucet_id();
+        let required = fills.iter().map(Fill::target_amount).sum::<u64>()
+            + posted.map_or(0, |order| order.source_amount());
+
+        let available = self.balance(account_id, faucet_id)?;
+        if available < required {
+            return Err(OrderBookError::InsufficientBalance {
+                faucet_id,
+                required,
+                available,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Part of `order` left after `fills` that rests in the order book, for the time in force that
+/// post what cannot be filled.
+pub fn resting_remainder(order: Order, fills: &[Fill]) -> Option<Order> {
+    match order.time_in_force() {
+        TimeInForce::GoodTilCancelled | TimeInForce::PostOnly => order.remainder(fills),
+        TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill => None,
+    }
+}
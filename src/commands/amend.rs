@@ -0,0 +1,102 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId,
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    notes::NoteId,
+    rpc::NodeRpcClient,
+    store::Store,
+    transactions::{request::TransactionRequest, OutputNote},
+    Client,
+};
+
+use miden_order_book::{
+    notes::create_order_note,
+    order::Order,
+    price::Price,
+    utils::{is_note_consumed, print_order_table},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Replace a resting order with a new price or amount")]
+pub struct AmendCmd {
+    /// Account that created the order
+    pub user: String,
+
+    /// Note id of the order to amend
+    pub note_id: String,
+
+    /// New price, as the amount requested per unit offered (e.g. 1.25 or 5/4)
+    #[arg(long, required_unless_present = "amount")]
+    pub price: Option<Price>,
+
+    /// New amount offered
+    #[arg(long)]
+    pub amount: Option<u64>,
+}
+
+impl AmendCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let note_id = NoteId::try_from_hex(self.note_id.as_str()).map_err(|e| e.to_string())?;
+
+        let note = client
+            .get_input_note(note_id)
+            .map_err(|e| format!("Unknown note {}: {}", note_id, e))?;
+        if is_note_consumed(&note) {
+            return Err(format!("Order {} was already consumed.", note_id));
+        }
+
+        let order = Order::try_from(&note)
+            .map_err(|e| format!("Note {} is not a valid order: {}", note_id, e))?;
+        if !order.can_be_reclaimed_by(account_id) {
+            return Err(format!(
+                "Order {} was not created by account {} and cannot be amended.",
+                note_id, account_id
+            ));
+        }
+
+        let amended_order = order
+            .amend(self.amount, self.price)
+            .map_err(|e| format!("Failed to amend order: {:?}", e))?;
+
+        // The reclaimed asset is available to the replacement note within the same transaction
+        let source_asset = order.source_asset();
+        let (account, _) = client.get_account(account_id).map_err(|e| e.to_string())?;
+        let balance = account
+            .vault()
+            .get_balance(source_asset.faucet_id())
+            .map_err(|e| e.to_string())?;
+        let required = amended_order.source_amount();
+        if balance.saturating_add(order.source_amount()) < required {
+            return Err("User does not have enough assets to amend this order.".to_string());
+        }
+
+        print_order_table("Order to replace:", &[order]);
+        print_order_table("Replacement order:", &[amended_order]);
+
+        // Reclaim the old note and post the replacement in one transaction, so the order is never
+        // missing from the book
+        let note = create_order_note(amended_order, account_id, client.rng())
+            .map_err(|e| format!("Failed to create swap note: {:?}", e))?;
+        let transaction_request = TransactionRequest::new()
+            .with_authenticated_input_notes(vec![(note_id, None)])
+            .with_own_output_notes(vec![OutputNote::Full(note)])
+            .map_err(|e| format!("Failed to create transaction request: {}", e))?;
+
+        let transaction = client
+            .new_transaction(account_id, transaction_request)
+            .map_err(|e| format!("Failed to create transaction: {}", e))?;
+        client
+            .submit_transaction(transaction)
+            .await
+            .map_err(|e| format!("Failed to submit transaction: {}", e))?;
+
+        println!("Order amended successfully.");
+        Ok(())
+    }
+}
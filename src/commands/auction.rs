@@ -0,0 +1,100 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient,
+    store::Store, Client,
+};
+
+use miden_order_book::{
+    client::OrderBookClient, constants::DEFAULT_AUCTION_WINDOW, order::clear_batch,
+    utils::print_batch_auction,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Clear the orders of a pair in a batch auction and settle them at a uniform price")]
+pub struct AuctionCmd {
+    /// Account settling the auction, it fronts the payments and refunds the makers
+    pub user: String,
+
+    /// Base faucet id
+    pub base_faucet: String,
+
+    /// Quote faucet id
+    pub quote_faucet: String,
+
+    /// Number of blocks orders are collected over
+    #[arg(long, default_value_t = DEFAULT_AUCTION_WINDOW)]
+    pub window: u32,
+
+    /// Only print the auction, without submitting a transaction
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl AuctionCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let base_faucet_id =
+            AccountId::from_hex(self.base_faucet.as_str()).map_err(|e| e.to_string())?;
+        let quote_faucet_id =
+            AccountId::from_hex(self.quote_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        let book = OrderBookClient::new(client)
+            .load_book(base_faucet_id, quote_faucet_id)
+            .map_err(|e| e.to_string())?;
+        let window_start = book.block_num().saturating_sub(self.window);
+
+        // Collect the orders of both sides of the pair included within the window, the settling
+        // account cannot fill its own orders
+        let orders = book
+            .asks()
+            .into_iter()
+            .chain(book.bids())
+            .filter(|order| order.creator() != Some(account_id))
+            .filter(|order| {
+                order
+                    .id()
+                    .and_then(|note_id| client.get_input_note(note_id).ok())
+                    .and_then(|note| {
+                        note.inclusion_proof()
+                            .map(|proof| proof.location().block_num())
+                    })
+                    .is_some_and(|included| included > window_start)
+            })
+            .collect();
+
+        let Some(auction) = clear_batch(base_faucet_id, quote_faucet_id, orders) else {
+            println!(
+                "Orders of the last {} blocks do not cross, nothing to clear.",
+                self.window
+            );
+            return Ok(());
+        };
+
+        print_batch_auction(&auction);
+
+        if self.dry_run {
+            println!("Dry run, no transaction submitted.");
+            return Ok(());
+        }
+
+        let settlement = auction
+            .settlement()
+            .map_err(|e| format!("Failed to settle the auction: {:?}", e))?;
+        OrderBookClient::new(client)
+            .settle_batch(account_id, &settlement)
+            .await
+            .map_err(|e| format!("Failed to settle the auction: {}", e))?;
+
+        println!(
+            "Settled {} orders at {}, {} makers refunded.",
+            settlement.fills().len(),
+            auction.clearing_price(),
+            settlement.refunds().len()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, notes::NoteId,
+    rpc::NodeRpcClient, store::Store, Client,
+};
+
+use miden_order_book::{
+    client::OrderBookClient,
+    conditional::{ConditionalBook, ConditionalStatus},
+    errors::OrderBookError,
+    iceberg::{IcebergBook, IcebergStatus},
+    notes::swap_tags,
+    order::Order,
+    twap::{TwapBook, TwapStatus},
+    utils::{get_notes_by_tag, is_note_consumed, print_order_table},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Cancel resting orders created by an account")]
+pub struct CancelCmd {
+    /// Account that created the orders
+    pub user: String,
+
+    /// Note ids of the orders to cancel
+    #[arg(
+        required_unless_present_any = ["all", "conditional", "twap"],
+        conflicts_with_all = ["all", "conditional", "twap"]
+    )]
+    pub note_ids: Vec<String>,
+
+    /// Cancel every order of the account in a market
+    #[arg(long, requires = "market")]
+    pub all: bool,
+
+    /// Offered and requested faucet ids of the orders to cancel, public and private alike
+    #[arg(long, num_args = 2, value_names = ["OFFERED_FAUCET", "REQUESTED_FAUCET"])]
+    pub market: Vec<String>,
+
+    /// Ids of the pending conditional orders to cancel
+    #[arg(long, num_args = 1.., conflicts_with = "all")]
+    pub conditional: Vec<u32>,
+
+    /// Ids of the running TWAP orders to cancel, their posted child orders are left resting
+    #[arg(long, num_args = 1.., conflicts_with = "all")]
+    pub twap: Vec<u32>,
+}
+
+impl CancelCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+
+        if !self.conditional.is_empty() || !self.twap.is_empty() {
+            return self.cancel_local_orders(account_id);
+        }
+
+        let note_ids = match self.market.as_slice() {
+            [offered, requested] if self.all => {
+                let offered = AccountId::from_hex(offered).map_err(|e| e.to_string())?;
+                let requested = AccountId::from_hex(requested).map_err(|e| e.to_string())?;
+                swap_tags(offered, requested)
+                    .map_err(|e| e.to_string())?
+                    .into_iter()
+                    .flat_map(|tag| get_notes_by_tag(client, tag))
+                    .filter(|note| !is_note_consumed(note))
+                    .filter(|note| {
+                        Order::try_from(note)
+                            .is_ok_and(|order| order.can_be_reclaimed_by(account_id))
+                    })
+                    .map(|note| note.id())
+                    .collect()
+            }
+            _ => self
+                .note_ids
+                .iter()
+                .map(|note_id| NoteId::try_from_hex(note_id).map_err(|e| e.to_string()))
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+
+        let cancellation = OrderBookClient::new(client)
+            .cancel(account_id, &note_ids)
+            .await
+            .map_err(|e| match e {
+                OrderBookError::NotCreator(refused) => format!(
+                    "Orders not created by account {} cannot be cancelled: {}",
+                    account_id,
+                    refused
+                        .iter()
+                        .map(|id| id.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                e => format!("Failed to cancel orders: {}", e),
+            })?;
+
+        // Notes consumed by a counterparty cannot be cancelled anymore, only the ones given
+        // explicitly are reported
+        if !self.all {
+            for note_id in &cancellation.consumed {
+                println!("Order {} was already consumed.", note_id);
+            }
+        }
+
+        let orders = cancellation.cancelled;
+        if orders.is_empty() {
+            println!("No orders to cancel.");
+            return Ok(());
+        }
+
+        print_order_table("Cancelled orders:", &orders);
+
+        // Icebergs whose resting slice is cancelled are not replenished anymore
+        let mut book = IcebergBook::load()?;
+        for note_id in orders.iter().filter_map(Order::id) {
+            if let Some(iceberg) = book.find_by_note_mut(&note_id.to_hex()) {
+                iceberg.status = IcebergStatus::Cancelled;
+            }
+        }
+        book.save()?;
+
+        println!("Cancelled {} orders.", orders.len());
+        Ok(())
+    }
+
+    /// Conditional and TWAP orders are only stored locally, so cancelling them needs no
+    /// transaction.
+    fn cancel_local_orders(&self, account_id: AccountId) -> Result<(), String> {
+        let mut book = ConditionalBook::load()?;
+        for id in &self.conditional {
+            let order = book
+                .get_mut(*id)
+                .filter(|order| order.account_id == account_id)
+                .ok_or_else(|| {
+                    format!("Account {} has no conditional order {}.", account_id, id)
+                })?;
+            if !order.is_pending() {
+                return Err(format!(
+                    "Conditional order {} is {:?} and cannot be cancelled.",
+                    id, order.status
+                ));
+            }
+            order.status = ConditionalStatus::Cancelled;
+        }
+        book.save()?;
+
+        let mut book = TwapBook::load()?;
+        for id in &self.twap {
+            let twap = book
+                .get_mut(*id)
+                .filter(|twap| twap.account_id == account_id)
+                .ok_or_else(|| format!("Account {} has no TWAP order {}.", account_id, id))?;
+            if !twap.is_running() {
+                return Err(format!(
+                    "TWAP order {} is {:?} and cannot be cancelled.",
+                    id, twap.status
+                ));
+            }
+            twap.status = TwapStatus::Cancelled;
+        }
+        book.save()?;
+
+        println!(
+            "Cancelled {} conditional and {} TWAP orders.",
+            self.conditional.len(),
+            self.twap.len()
+        );
+        Ok(())
+    }
+}
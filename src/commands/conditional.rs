@@ -0,0 +1,99 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, assets::FungibleAsset, auth::TransactionAuthenticator, crypto::FeltRng,
+    notes::NoteType, rpc::NodeRpcClient, store::Store, transactions::build_swap_tag, Client,
+};
+
+use miden_order_book::{
+    conditional::{ConditionalBook, ConditionalKind},
+    order::TimeInForce,
+    price::Price,
+    utils::print_conditional_orders,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Add an order submitted once the best price of its market crosses a trigger price")]
+pub struct ConditionalCmd {
+    /// Account executing the order
+    pub user: String,
+
+    /// Target faucet id
+    pub target_faucet: String,
+
+    /// Target asset amount
+    pub target_amount: u64,
+
+    /// Source faucet id
+    pub source_faucet: String,
+
+    /// Source asset amount
+    pub source_amount: u64,
+
+    /// Submit the order once the best price falls to this price, as the amount of target asset
+    /// received per unit of source asset (e.g. 1.25 or 5/4)
+    #[arg(
+        long,
+        required_unless_present = "take_profit",
+        conflicts_with = "take_profit"
+    )]
+    pub stop_loss: Option<Price>,
+
+    /// Submit the order once the best price rises to this price, as the amount of target asset
+    /// received per unit of source asset
+    #[arg(long)]
+    pub take_profit: Option<Price>,
+
+    /// Time in force of the order once submitted
+    #[arg(long, value_enum, default_value = "ioc")]
+    pub time_in_force: TimeInForce,
+}
+
+impl ConditionalCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let source_faucet_id =
+            AccountId::from_hex(self.source_faucet.as_str()).map_err(|e| e.to_string())?;
+        let target_faucet_id =
+            AccountId::from_hex(self.target_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        let (kind, trigger_price) = match (self.stop_loss, self.take_profit) {
+            (Some(price), None) => (ConditionalKind::StopLoss, price),
+            (None, Some(price)) => (ConditionalKind::TakeProfit, price),
+            _ => return Err("Set either a stop-loss or a take-profit price.".to_string()),
+        };
+
+        let source_asset =
+            FungibleAsset::new(source_faucet_id, self.source_amount).map_err(|e| e.to_string())?;
+        let target_asset =
+            FungibleAsset::new(target_faucet_id, self.target_amount).map_err(|e| e.to_string())?;
+
+        // Track the tag of the orders the conditional order would fill against, so the watcher
+        // sees the book
+        let tag = build_swap_tag(NoteType::Public, target_faucet_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        client.add_note_tag(tag).map_err(|e| e.to_string())?;
+
+        let mut book = ConditionalBook::load()?;
+        let id = book.add(
+            account_id,
+            source_asset,
+            target_asset,
+            kind,
+            trigger_price,
+            self.time_in_force,
+        );
+        book.save()?;
+
+        let order = book
+            .get_mut(id)
+            .expect("conditional order was just added")
+            .clone();
+        print_conditional_orders("Conditional order added:", &[order]);
+        println!("Run `watch` to submit it once triggered.");
+        Ok(())
+    }
+}
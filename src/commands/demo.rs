@@ -1,12 +1,19 @@
 use std::{thread::sleep, time::Duration};
 
-use crate::commands::{list::ListCmd, order::OrderCmd, query::QueryCmd, setup::SetupCmd};
+use super::{list::ListCmd, order::OrderCmd, query::QueryCmd, setup::SetupCmd};
 use clap::Parser;
 use colored::*;
 use log::{info, warn};
 use miden_client::{
     auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient, store::Store, Client,
 };
+use miden_order_book::{
+    constants::{
+        DEFAULT_DEPTH_LEVELS, DEFAULT_MAX_HOPS, DEFAULT_MAX_SLIPPAGE_BPS,
+        DEFAULT_SELECTION_BUDGET_MS,
+    },
+    order::SelfTradePrevention,
+};
 
 #[derive(Debug, Clone, Parser)]
 #[clap(about = "Demo the full order book flow")]
@@ -47,17 +54,32 @@ impl DemoCmd {
         info!("Listing available orders...");
         let list = ListCmd {
             tags: vec![clob.swap_1_2_tag.into(), clob.swap_2_1_tag.into()],
+            hide_expired: false,
+            raw: false,
+            tick: None,
+            levels: DEFAULT_DEPTH_LEVELS,
         };
         list.execute(client)
             .map_err(|e| format!("Listing failed: {}", e))?;
 
         info!("Placing a new order...");
         let order = OrderCmd {
-            user: clob.user.to_string(),
-            target_faucet: clob.faucet1.to_string(),
-            target_amount: 10,
-            source_faucet: clob.faucet2.to_string(),
-            source_amount: 20,
+            action: None,
+            user: Some(clob.user.to_string()),
+            target_faucet: Some(clob.faucet1.to_string()),
+            target_amount: Some(10),
+            source_faucet: Some(clob.faucet2.to_string()),
+            source_amount: Some(20),
+            selection_budget_ms: DEFAULT_SELECTION_BUDGET_MS,
+            compare_selection: true,
+            market: false,
+            max_slippage_bps: DEFAULT_MAX_SLIPPAGE_BPS,
+            time_in_force: None,
+            expires_in: None,
+            route: false,
+            max_hops: DEFAULT_MAX_HOPS,
+            self_trade: SelfTradePrevention::Skip,
+            private: false,
         };
         order
             .execute(client)
@@ -0,0 +1,74 @@
+use std::fs;
+
+use clap::Parser;
+use miden_client::{
+    auth::TransactionAuthenticator, crypto::FeltRng, notes::NoteId, rpc::NodeRpcClient,
+    store::Store, Client,
+};
+use miden_objects::utils::Serializable;
+
+use miden_order_book::{
+    client::OrderBookClient,
+    sharing::{ShareDirection, SharedOrderBook},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Export the note of an own order to share it off-chain with a counterparty")]
+pub struct ExportCmd {
+    /// Id of the order note, as created by the order command
+    pub note_id: String,
+
+    /// Account id or name of the counterparty the note is shared with
+    #[arg(long)]
+    pub to: String,
+
+    /// File the note is written to, `<note id>.mno` by default
+    #[arg(long)]
+    pub file: Option<String>,
+}
+
+impl ExportCmd {
+    pub fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let note_id = NoteId::try_from_hex(&self.note_id).map_err(|e| e.to_string())?;
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+
+        let note_file = OrderBookClient::new(client)
+            .export_note(note_id)
+            .map_err(|e| e.to_string())?;
+        let file = self
+            .file
+            .clone()
+            .unwrap_or_else(|| format!("{}.mno", note_id.to_hex()));
+        fs::write(&file, note_file.to_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", file, e))?;
+
+        let mut shares = SharedOrderBook::load()?;
+        let note_id = note_id.to_hex();
+        let previous: Vec<String> = shares
+            .recipients(&note_id)
+            .into_iter()
+            .filter(|counterparty| *counterparty != self.to)
+            .map(str::to_string)
+            .collect();
+        shares.record(
+            note_id.clone(),
+            self.to.clone(),
+            ShareDirection::Sent,
+            file.clone(),
+            block_num,
+        );
+        shares.save()?;
+
+        println!(
+            "Order note {} exported to {} for {}.",
+            note_id, file, self.to
+        );
+        if !previous.is_empty() {
+            println!("Also shared with: {}", previous.join(", "));
+        }
+        Ok(())
+    }
+}
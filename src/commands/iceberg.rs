@@ -0,0 +1,195 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId,
+    assets::FungibleAsset,
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    notes::NoteType,
+    rpc::NodeRpcClient,
+    store::Store,
+    transactions::{build_swap_tag, request::TransactionRequest, OutputNote},
+    Client,
+};
+use miden_objects::Digest;
+
+use miden_order_book::{
+    iceberg::{Iceberg, IcebergBook, IcebergStatus},
+    notes::create_order_note,
+    utils::{get_notes_by_tag, is_note_consumed, print_icebergs},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Post an order showing only a slice of its size in the order book")]
+pub struct IcebergCmd {
+    /// Account posting the order
+    pub user: String,
+
+    /// Target faucet id
+    pub target_faucet: String,
+
+    /// Target asset amount
+    pub target_amount: u64,
+
+    /// Source faucet id
+    pub source_faucet: String,
+
+    /// Source asset amount
+    pub source_amount: u64,
+
+    /// Source asset amount offered by each visible slice
+    #[arg(long)]
+    pub visible: u64,
+}
+
+impl IcebergCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let source_faucet_id =
+            AccountId::from_hex(self.source_faucet.as_str()).map_err(|e| e.to_string())?;
+        let target_faucet_id =
+            AccountId::from_hex(self.target_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        if self.visible == 0 || self.visible > self.source_amount {
+            return Err("The visible amount must be between 1 and the source amount.".to_string());
+        }
+
+        // Check if user has balance for the whole order
+        let (account, _) = client.get_account(account_id).map_err(|e| e.to_string())?;
+        if account
+            .vault()
+            .get_balance(source_faucet_id)
+            .map_err(|e| e.to_string())?
+            < self.source_amount
+        {
+            return Err("User does not have enough assets to post this order.".to_string());
+        }
+
+        let source_asset =
+            FungibleAsset::new(source_faucet_id, self.source_amount).map_err(|e| e.to_string())?;
+        let target_asset =
+            FungibleAsset::new(target_faucet_id, self.target_amount).map_err(|e| e.to_string())?;
+
+        // Track the tag of the slices so their consumption is picked up on sync
+        let tag = build_swap_tag(NoteType::Public, source_faucet_id, target_faucet_id)
+            .map_err(|e| e.to_string())?;
+        client.add_note_tag(tag).map_err(|e| e.to_string())?;
+
+        let mut book = IcebergBook::load()?;
+        let id = book.add(account_id, source_asset, target_asset, self.visible);
+        let iceberg = book.get_mut(id).expect("iceberg was just added");
+        let result = Self::post_next_slice(iceberg, client).await;
+        let iceberg = iceberg.clone();
+        book.save()?;
+        result?;
+
+        print_icebergs("Iceberg order posted:", &[iceberg]);
+        Ok(())
+    }
+
+    /// Follows the resting slice of every open iceberg, posting the next slice once it has been
+    /// filled. The unfilled part of a partially filled slice stays the resting slice.
+    pub async fn replenish<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let mut book = IcebergBook::load()?;
+
+        let mut result = Ok(());
+        for iceberg in book.icebergs.iter_mut().filter(|iceberg| iceberg.is_open()) {
+            result = Self::replenish_iceberg(iceberg, client).await;
+            if result.is_err() {
+                break;
+            }
+        }
+
+        book.save()?;
+        result
+    }
+
+    async fn replenish_iceberg<
+        N: NodeRpcClient,
+        R: FeltRng,
+        S: Store,
+        A: TransactionAuthenticator,
+    >(
+        iceberg: &mut Iceberg,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let Some(note_id) = iceberg.note_id.clone() else {
+            return Self::post_next_slice(iceberg, client).await;
+        };
+
+        let tag = build_swap_tag(
+            NoteType::Public,
+            iceberg.source_faucet_id,
+            iceberg.target_faucet_id,
+        )
+        .map_err(|e| e.to_string())?;
+        let notes = get_notes_by_tag(client, tag);
+
+        // The slice is still resting, or has not been committed yet
+        let consumed = notes
+            .iter()
+            .find(|note| note.id().to_hex() == note_id)
+            .is_some_and(is_note_consumed);
+        if !consumed {
+            return Ok(());
+        }
+
+        let leftover = notes.iter().find(|note| {
+            !is_note_consumed(note) && iceberg.is_leftover(note.details().serial_num())
+        });
+        match leftover {
+            Some(note) => {
+                iceberg.note_id = Some(note.id().to_hex());
+                iceberg.serial_num = Some(Digest::from(note.details().serial_num()).to_hex());
+                Ok(())
+            }
+            None => Self::post_next_slice(iceberg, client).await,
+        }
+    }
+
+    /// Posts the next slice of `iceberg`, completing it when nothing is left to post.
+    async fn post_next_slice<
+        N: NodeRpcClient,
+        R: FeltRng,
+        S: Store,
+        A: TransactionAuthenticator,
+    >(
+        iceberg: &mut Iceberg,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let Some(slice) = iceberg.next_slice() else {
+            iceberg.note_id = None;
+            iceberg.serial_num = None;
+            iceberg.status = IcebergStatus::Completed;
+            return Ok(());
+        };
+
+        let note = create_order_note(slice, iceberg.account_id, client.rng())
+            .map_err(|e| format!("Failed to create swap note: {:?}", e))?;
+        let note_id = note.id().to_hex();
+        let serial_num = note.recipient().serial_num();
+
+        let transaction_request = TransactionRequest::new()
+            .with_own_output_notes(vec![OutputNote::Full(note)])
+            .map_err(|e| format!("Failed to create transaction request: {}", e))?;
+        let transaction = client
+            .new_transaction(iceberg.account_id, transaction_request)
+            .map_err(|e| format!("Failed to create transaction: {}", e))?;
+        client
+            .submit_transaction(transaction)
+            .await
+            .map_err(|e| format!("Failed to submit transaction: {}", e))?;
+
+        iceberg.post_slice(&slice, note_id, serial_num);
+        println!(
+            "Posted a slice of iceberg {}, {} left hidden.",
+            iceberg.id, iceberg.hidden_amount
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+
+use clap::Parser;
+use miden_client::{
+    auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient, store::Store,
+    transactions::build_swap_tag, Client,
+};
+use miden_objects::{notes::NoteFile, utils::Deserializable};
+
+use miden_order_book::{
+    client::OrderBookClient,
+    sharing::{ShareDirection, SharedOrderBook},
+    utils::print_order_table,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Import an order note shared off-chain, to list and fill it like public orders")]
+pub struct ImportCmd {
+    /// Note file received from the creator of the order
+    pub file: String,
+
+    /// Account id or name of the counterparty the note was received from, the creator of the
+    /// order by default
+    #[arg(long)]
+    pub from: Option<String>,
+}
+
+impl ImportCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let bytes =
+            fs::read(&self.file).map_err(|e| format!("Failed to read {}: {}", self.file, e))?;
+        let note_file = NoteFile::read_from_bytes(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", self.file, e))?;
+
+        let mut book_client = OrderBookClient::new(client);
+        let order = book_client
+            .import_note(note_file)
+            .await
+            .map_err(|e| e.to_string())?;
+        print_order_table("Imported order:", &[order]);
+
+        let tag = build_swap_tag(
+            order.note_type(),
+            order.source_asset().faucet_id(),
+            order.target_asset().faucet_id(),
+        )
+        .map_err(|e| e.to_string())?;
+        println!("List its market with `list {}`.", tag.inner());
+
+        // The note is listed once its commitment is known, after the next sync
+        let block_num = book_client.sync().await.map_err(|e| e.to_string())?;
+
+        let counterparty = self
+            .from
+            .clone()
+            .or_else(|| order.creator().map(|creator| creator.to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let note_id = order.id().map(|id| id.to_hex()).unwrap_or_default();
+        let mut shares = SharedOrderBook::load()?;
+        shares.record(
+            note_id,
+            counterparty,
+            ShareDirection::Received,
+            self.file.clone(),
+            block_num,
+        );
+        shares.save()?;
+
+        Ok(())
+    }
+}
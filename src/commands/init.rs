@@ -1,5 +1,8 @@
-use crate::constants::{ACCOUNTS_DIR, CLOB_DATA_FILE_PATH, DB_FILE_PATH};
 use clap::Parser;
+use miden_order_book::constants::{
+    ACCOUNTS_DIR, CLOB_DATA_FILE_PATH, CONDITIONAL_ORDERS_FILE_PATH, DB_FILE_PATH,
+    ICEBERGS_FILE_PATH, OTC_OFFERS_FILE_PATH, SHARED_ORDERS_FILE_PATH, TWAPS_FILE_PATH,
+};
 use std::{
     fs::{self, File},
     path::Path,
@@ -14,6 +17,11 @@ impl InitCmd {
         self.remove_file_if_exists(DB_FILE_PATH)?;
         self.create_file(DB_FILE_PATH)?;
         self.remove_file_if_exists(CLOB_DATA_FILE_PATH)?;
+        self.remove_file_if_exists(ICEBERGS_FILE_PATH)?;
+        self.remove_file_if_exists(CONDITIONAL_ORDERS_FILE_PATH)?;
+        self.remove_file_if_exists(TWAPS_FILE_PATH)?;
+        self.remove_file_if_exists(SHARED_ORDERS_FILE_PATH)?;
+        self.remove_file_if_exists(OTC_OFFERS_FILE_PATH)?;
         self.remove_folder_if_exists(ACCOUNTS_DIR)?;
         println!("State successfully initialized.");
         Ok(())
@@ -1,33 +1,154 @@
-use crate::{
-    order::{sort_orders, Order},
-    utils::{get_notes_by_tag, print_order_table},
-};
 use clap::Parser;
 use miden_client::{
-    auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient, store::Store, Client,
+    accounts::AccountId,
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    notes::NoteId,
+    rpc::NodeRpcClient,
+    store::{InputNoteRecord, Store},
+    Client,
+};
+use miden_order_book::{
+    book::OrderBook,
+    client::OrderBookClient,
+    constants::DEFAULT_DEPTH_LEVELS,
+    errors::SwapNoteError,
+    order::{sort_orders, Order},
+    price::Price,
+    utils::{get_notes_by_tag, print_depth, print_order_table},
 };
 
 #[derive(Debug, Clone, Parser)]
-#[clap(about = "Create a new account and login")]
+#[clap(about = "List the orders of the markets of the given tags")]
 pub struct ListCmd {
     // tags
     pub tags: Vec<u32>,
+
+    /// Do not list orders past their expiration block
+    #[arg(long)]
+    pub hide_expired: bool,
+
+    /// List one row per note instead of the depth of each market
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Price step the depth is aggregated to, in quote per base (e.g. 0.01 or 1/100)
+    #[arg(long, conflicts_with = "raw")]
+    pub tick: Option<Price>,
+
+    /// Maximum number of price levels listed on each side
+    #[arg(long, default_value_t = DEFAULT_DEPTH_LEVELS, conflicts_with = "raw")]
+    pub levels: usize,
 }
 
 impl ListCmd {
     pub fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        if self.raw {
+            return self.list_notes(client);
+        }
+
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+
+        // Tags only cover one side of a market, the base asset is the one offered under the first
+        // tag listing the market. Invalid notes are reported when loading the book.
+        let mut markets: Vec<(AccountId, AccountId, usize)> = Vec::new();
+        for tag in self.tags.clone() {
+            for order in get_notes_by_tag(client, tag.into())
+                .iter()
+                .filter_map(|note| Order::try_from(note).ok())
+            {
+                let offered = order.source_asset().faucet_id();
+                let requested = order.target_asset().faucet_id();
+                let expired = order.is_expired(block_num) as usize;
+                match markets.iter_mut().find(|(base, quote, _)| {
+                    (*base, *quote) == (offered, requested)
+                        || (*base, *quote) == (requested, offered)
+                }) {
+                    Some((_, _, expired_orders)) => *expired_orders += expired,
+                    None => markets.push((offered, requested, expired)),
+                }
+            }
+        }
+
+        let book_client = OrderBookClient::new(client);
+        for (base_faucet_id, quote_faucet_id, expired_orders) in markets {
+            let book = book_client
+                .load_book(base_faucet_id, quote_faucet_id)
+                .map_err(|e| e.to_string())?;
+
+            let title = format!(
+                "Depth of market {}/{} at block {}, prices in quote per base:",
+                base_faucet_id, quote_faucet_id, block_num
+            );
+            print_depth(title.as_str(), &book, self.tick, self.levels);
+
+            if !self.hide_expired && expired_orders > 0 {
+                println!(
+                    "{} expired orders are not part of the book, list them with --raw.",
+                    expired_orders
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the notes of every tag, one row per note.
+    fn list_notes<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
         &self,
         client: &Client<N, R, S, A>,
     ) -> Result<(), String> {
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+
         for tag in self.tags.clone() {
-            let notes = get_notes_by_tag(&client, tag.into());
-            let orders: Vec<Order> = notes.into_iter().map(Order::from).collect();
+            let (orders, skipped) = Self::decode(get_notes_by_tag(client, tag.into()));
+
+            // Expired orders can only be reclaimed by their creator, they are listed apart
+            let (expired_orders, orders): (Vec<Order>, Vec<Order>) = orders
+                .into_iter()
+                .partition(|order| order.is_expired(block_num));
+            let expired_orders = sort_orders(expired_orders);
+
+            // Notes of a tag offer the same asset, they are the asks of their market
+            let active_orders: Vec<Order> = OrderBook::markets(block_num, orders)
+                .iter()
+                .flat_map(OrderBook::asks)
+                .collect();
 
-            let sorted_orders = sort_orders(orders);
             let title = format!("Relevant orders for tag {}:", tag);
-            print_order_table(title.as_str(), &sorted_orders);
+            print_order_table(title.as_str(), &active_orders);
+
+            if !self.hide_expired && !expired_orders.is_empty() {
+                let title = format!("Expired orders for tag {} at block {}:", tag, block_num);
+                print_order_table(title.as_str(), &expired_orders);
+            }
+            if !skipped.is_empty() {
+                println!(
+                    "Skipped notes of tag {} that are not valid swap notes:",
+                    tag
+                );
+                for (note_id, err) in skipped {
+                    println!("  {}: {}", note_id, err);
+                }
+            }
         }
 
         Ok(())
     }
+
+    /// Decodes the orders of `notes`, returning apart the notes that are not valid swap notes.
+    fn decode(notes: Vec<InputNoteRecord>) -> (Vec<Order>, Vec<(NoteId, SwapNoteError)>) {
+        let mut orders = Vec::new();
+        let mut skipped = Vec::new();
+        for note in notes {
+            match Order::try_from(&note) {
+                Ok(order) => orders.push(order),
+                Err(err) => skipped.push((note.id(), err)),
+            }
+        }
+        (orders, skipped)
+    }
 }
@@ -0,0 +1,88 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient,
+    store::Store, Client,
+};
+
+use miden_order_book::{
+    client::OrderBookClient,
+    order::{cross_orders, Cross, Fill},
+    utils::print_crosses,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Match crossing orders of both sides of a pair and keep the spread")]
+pub struct MatcherCmd {
+    /// Matcher account consuming the crossing orders
+    pub user: String,
+
+    /// Faucet id of the asset the matcher pays first, it is paid back within the same transaction
+    pub faucet_a: String,
+
+    /// Faucet id of the other asset of the pair
+    pub faucet_b: String,
+
+    /// Only print the crossing orders, without submitting a transaction
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl MatcherCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let faucet_a = AccountId::from_hex(self.faucet_a.as_str()).map_err(|e| e.to_string())?;
+        let faucet_b = AccountId::from_hex(self.faucet_b.as_str()).map_err(|e| e.to_string())?;
+        let book = OrderBookClient::new(client)
+            .load_book(faucet_a, faucet_b)
+            .map_err(|e| e.to_string())?;
+
+        // Orders asking for asset A are paid first, then orders offering asset A pay it back
+        let first_orders = book.offers(faucet_b);
+        let second_orders = book.offers(faucet_a);
+
+        let crosses = cross_orders(first_orders, second_orders);
+        if crosses.is_empty() {
+            println!("No crossing orders found.");
+            return Ok(());
+        }
+
+        print_crosses(&crosses);
+
+        if self.dry_run {
+            println!("Dry run, no transaction submitted.");
+            return Ok(());
+        }
+
+        // Each cross pays back what it spent, so the largest first payment is enough
+        let required = crosses
+            .iter()
+            .map(|cross| cross.fills()[0].target_amount())
+            .max()
+            .unwrap_or(0);
+        let (account, _) = client.get_account(account_id).map_err(|e| e.to_string())?;
+        if account
+            .vault()
+            .get_balance(faucet_a)
+            .map_err(|e| e.to_string())?
+            < required
+        {
+            return Err(format!(
+                "Matcher needs {} of {} to cross the orders.",
+                required, faucet_a
+            ));
+        }
+
+        let fills: Vec<Fill> = crosses.iter().flat_map(Cross::fills).collect();
+        OrderBookClient::new(client)
+            .execute_fills(account_id, &fills, None)
+            .await
+            .map_err(|e| format!("Failed to match orders: {}", e))?;
+
+        println!("Matched {} crossing order pairs.", crosses.len());
+        Ok(())
+    }
+}
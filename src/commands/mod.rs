@@ -1,8 +1,25 @@
+pub mod amend;
+pub mod auction;
+pub mod cancel;
+pub mod conditional;
 pub mod demo;
+pub mod export;
+pub mod iceberg;
+pub mod import;
 pub mod init;
 pub mod list;
 pub mod login;
+pub mod matcher;
+pub mod offers;
+pub mod open_orders;
 pub mod order;
+pub mod otc;
 pub mod query;
+pub mod quote;
+pub mod reclaim;
 pub mod setup;
+pub mod shared;
 pub mod sync;
+pub mod ticker;
+pub mod twap;
+pub mod watch;
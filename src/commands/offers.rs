@@ -0,0 +1,124 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, notes::NoteId,
+    rpc::NodeRpcClient, store::Store, Client,
+};
+
+use super::order::OrderCmd;
+use miden_order_book::{
+    client::OrderBookClient, order::Order, otc::OtcBook, utils::print_order_table,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "List, accept or decline the OTC orders addressed to an account")]
+pub struct OffersCmd {
+    /// Account the orders are addressed to
+    pub user: String,
+
+    /// Fill the order of this note
+    #[arg(long, conflicts_with = "decline")]
+    pub accept: Option<String>,
+
+    /// Amount of the requested asset paid when accepting, the whole order by default
+    #[arg(long, requires = "accept")]
+    pub amount: Option<u64>,
+
+    /// Stop listing the order of this note
+    #[arg(long)]
+    pub decline: Option<String>,
+
+    /// Also list the declined orders
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl OffersCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let offers = OrderBookClient::new(client)
+            .offers(account_id)
+            .map_err(|e| e.to_string())?;
+
+        if let Some(note_id) = &self.accept {
+            let offer = Self::find(&offers, note_id)?;
+            return self.accept(offer, account_id, client).await;
+        }
+
+        let mut otc = OtcBook::load()?;
+        if let Some(note_id) = &self.decline {
+            let offer = Self::find(&offers, note_id)?;
+            print_order_table("OTC order to decline:", &[offer]);
+            if !OrderCmd::confirm("Do you want to decline the order?")
+                .map_err(|e| format!("{:?}", e))?
+            {
+                println!("Execution cancelled by user.");
+                return Ok(());
+            }
+
+            let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+            let note_id = offer.id().map(|id| id.to_hex()).unwrap_or_default();
+            otc.decline(note_id, account_id, block_num);
+            otc.save()?;
+            println!("Order declined, it is no longer listed. Its creator can still cancel it.");
+            return Ok(());
+        }
+
+        let offers = if self.all {
+            offers
+        } else {
+            otc.pending(account_id, offers)
+        };
+        let title = format!("OTC orders addressed to {}:", account_id);
+        print_order_table(title.as_str(), &offers);
+        for offer in &offers {
+            if let (Some(note_id), Some(creator)) = (offer.id(), offer.creator()) {
+                println!("Note {} created by {}", note_id.to_hex(), creator);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fills `offer` through the confirmation path of the order command.
+    async fn accept<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        offer: Order,
+        account_id: AccountId,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let fill = offer
+            .fill(self.amount.unwrap_or(offer.target_amount()))
+            .map_err(|e| format!("Failed to fill order: {:?}", e))?;
+
+        // Check if user has balance
+        let balance = OrderBookClient::new(client)
+            .balance(account_id, offer.target_asset().faucet_id())
+            .map_err(|e| e.to_string())?;
+        if balance < fill.target_amount() {
+            return Err("User does not have enough assets to execute this order.".to_string());
+        }
+
+        OrderCmd::fill_success(vec![fill], None, &[], account_id, true, client)
+            .await
+            .map_err(|e| format!("Failed in fill success: {}", e))
+    }
+
+    /// Offer of `note_id` among `offers`.
+    fn find(offers: &[Order], note_id: &str) -> Result<Order, String> {
+        let note_id = NoteId::try_from_hex(note_id).map_err(|e| e.to_string())?;
+        offers
+            .iter()
+            .find(|offer| offer.id() == Some(note_id))
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "No active OTC order addressed to the account in note {}.",
+                    note_id
+                )
+            })
+    }
+}
@@ -0,0 +1,58 @@
+use clap::Parser;
+
+use miden_client::accounts::AccountId;
+
+use miden_order_book::{
+    conditional::ConditionalBook,
+    iceberg::IcebergBook,
+    twap::TwapBook,
+    utils::{print_conditional_orders, print_icebergs, print_twaps},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "List the orders managed locally by the client")]
+pub struct OpenOrdersCmd {
+    /// Only list the orders of this account
+    pub user: Option<String>,
+
+    /// Also list completed, triggered and cancelled orders
+    #[arg(long)]
+    pub all: bool,
+}
+
+impl OpenOrdersCmd {
+    pub fn execute(&self) -> Result<(), String> {
+        let account_id = self
+            .user
+            .as_deref()
+            .map(AccountId::from_hex)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let is_listed = |owner: AccountId, open: bool| {
+            account_id.map_or(true, |account_id| account_id == owner) && (open || self.all)
+        };
+
+        let icebergs: Vec<_> = IcebergBook::load()?
+            .icebergs
+            .into_iter()
+            .filter(|iceberg| is_listed(iceberg.account_id, iceberg.is_open()))
+            .collect();
+        print_icebergs("Iceberg orders:", &icebergs);
+
+        let conditional_orders: Vec<_> = ConditionalBook::load()?
+            .orders
+            .into_iter()
+            .filter(|order| is_listed(order.account_id, order.is_pending()))
+            .collect();
+        print_conditional_orders("Conditional orders:", &conditional_orders);
+
+        let twaps: Vec<_> = TwapBook::load()?
+            .twaps
+            .into_iter()
+            .filter(|twap| is_listed(twap.account_id, twap.is_running()))
+            .collect();
+        print_twaps("TWAP orders:", &twaps);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use std::io::{self, Write};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
 
 use miden_client::{
     accounts::AccountId,
@@ -8,38 +11,115 @@ use miden_client::{
     notes::{NoteId, NoteType},
     rpc::NodeRpcClient,
     store::Store,
-    transactions::{
-        build_swap_tag,
-        request::{SwapTransactionData, TransactionRequest},
-    },
+    transactions::TransactionResult,
     Client,
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use crate::{
-    errors::OrderError,
-    order::{match_orders, sort_orders, Order},
-    utils::{get_notes_by_tag, print_balance_update, print_order_table},
+use super::{
+    amend::AmendCmd, cancel::CancelCmd, conditional::ConditionalCmd, iceberg::IcebergCmd,
+    otc::OtcCmd, quote::QuoteCmd, twap::TwapCmd,
+};
+use miden_order_book::{
+    client::OrderBookClient,
+    constants::{DEFAULT_MAX_HOPS, DEFAULT_MAX_SLIPPAGE_BPS, DEFAULT_SELECTION_BUDGET_MS},
+    errors::{OrderBookError, OrderError},
+    order::{
+        active_orders, fill_order, prevent_self_trade, selection_report, sweep_orders, Fill,
+        MarketAmount, Order, SelfTradePrevention, TimeInForce,
+    },
+    router::{find_route, Route},
+    utils::{
+        get_notes_by_tag, is_note_consumed, orders_from_notes, print_balance_update,
+        print_market_sweep, print_order_table, print_route, print_selection_report,
+    },
 };
 
+#[derive(Debug, Clone, Subcommand)]
+pub enum OrderAction {
+    Cancel(CancelCmd),
+    Amend(AmendCmd),
+    Iceberg(IcebergCmd),
+    Conditional(ConditionalCmd),
+    Twap(TwapCmd),
+    Quote(QuoteCmd),
+    Otc(OtcCmd),
+}
+
 #[derive(Debug, Clone, Parser)]
-#[command(about = "Execute an order")]
+#[command(
+    about = "Execute an order",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct OrderCmd {
+    #[command(subcommand)]
+    pub action: Option<OrderAction>,
+
     /// Account executing the order
-    pub user: String,
+    #[arg(required = true)]
+    pub user: Option<String>,
 
     /// Target faucet id
-    pub target_faucet: String,
+    #[arg(required = true)]
+    pub target_faucet: Option<String>,
 
     /// Target asset amount
-    pub target_amount: u64,
+    #[arg(required = true)]
+    pub target_amount: Option<u64>,
 
     /// Source faucet id
-    pub source_faucet: String,
+    #[arg(required = true)]
+    pub source_faucet: Option<String>,
 
     /// Source asset amount
-    pub source_amount: u64,
+    #[arg(required = true)]
+    pub source_amount: Option<u64>,
+
+    /// Time budget in milliseconds for the optimal order selection
+    #[arg(long, default_value_t = DEFAULT_SELECTION_BUDGET_MS)]
+    pub selection_budget_ms: u64,
+
+    /// Print a comparison of the greedy and optimal order selection
+    #[arg(long)]
+    pub compare_selection: bool,
+
+    /// Execute as a market order sweeping the book from the best price. Set the target amount to
+    /// 0 to spend the source amount, or the source amount to 0 to receive the target amount
+    #[arg(long)]
+    pub market: bool,
+
+    /// Maximum slippage of a market order in basis points relative to the best price
+    #[arg(long, default_value_t = DEFAULT_MAX_SLIPPAGE_BPS)]
+    pub max_slippage_bps: u32,
+
+    /// Time in force of a limit order, executed without prompts. Without it the order is filled
+    /// entirely or, after confirmation, added to the order book
+    #[arg(long, value_enum, conflicts_with = "market")]
+    pub time_in_force: Option<TimeInForce>,
+
+    /// Number of blocks after which the order posted to the order book expires
+    #[arg(long, conflicts_with = "market")]
+    pub expires_in: Option<u32>,
+
+    /// Route the order through the best path across markets, instead of the direct market only.
+    /// Orders without a direct market are routed too, unless they have a time in force
+    #[arg(long, conflicts_with_all = ["market", "time_in_force"])]
+    pub route: bool,
+
+    /// Maximum number of markets a routed order goes through
+    #[arg(long, default_value_t = DEFAULT_MAX_HOPS)]
+    pub max_hops: usize,
+
+    /// What happens to the orders of the same account that the order would fill. Routed orders
+    /// always skip them
+    #[arg(long, value_enum, default_value = "skip")]
+    pub self_trade: SelfTradePrevention,
+
+    /// Post the order in a private note, only listed by the counterparties it is exported to
+    #[arg(long, conflicts_with_all = ["market", "route"])]
+    pub private: bool,
 }
 
 impl OrderCmd {
@@ -47,158 +127,390 @@ impl OrderCmd {
         &self,
         client: &mut Client<N, R, S, A>,
     ) -> Result<(), String> {
+        if let Some(action) = &self.action {
+            return match action {
+                OrderAction::Cancel(cancel) => cancel.execute(client).await,
+                OrderAction::Amend(amend) => amend.execute(client).await,
+                OrderAction::Iceberg(iceberg) => iceberg.execute(client).await,
+                OrderAction::Conditional(conditional) => conditional.execute(client).await,
+                OrderAction::Twap(twap) => twap.execute(client).await,
+                OrderAction::Quote(quote) => quote.execute(client).await,
+                OrderAction::Otc(otc) => otc.execute(client).await,
+            };
+        }
+
+        // Clap requires the order arguments when no subcommand is given
+        let (
+            Some(user),
+            Some(target_faucet),
+            Some(target_amount),
+            Some(source_faucet),
+            Some(source_amount),
+        ) = (
+            &self.user,
+            &self.target_faucet,
+            self.target_amount,
+            &self.source_faucet,
+            self.source_amount,
+        )
+        else {
+            return Err("Missing order arguments.".to_string());
+        };
+
         // Parse id's
-        let account_id = AccountId::from_hex(self.user.as_str()).unwrap();
-        let source_faucet_id = AccountId::from_hex(self.source_faucet.as_str()).unwrap();
-        let target_faucet_id = AccountId::from_hex(self.target_faucet.as_str()).unwrap();
+        let account_id = AccountId::from_hex(user.as_str()).unwrap();
+        let source_faucet_id = AccountId::from_hex(source_faucet.as_str()).unwrap();
+        let target_faucet_id = AccountId::from_hex(target_faucet.as_str()).unwrap();
 
         // Check if user has balance
-        let (account, _) = client.get_account(account_id).unwrap();
-        if account.vault().get_balance(source_faucet_id).unwrap() < self.source_amount {
-            panic!("User does not have enough assets to execute this order.");
+        let book_client = OrderBookClient::new(client);
+        let balance = book_client
+            .balance(account_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        if balance < source_amount {
+            return Err("User does not have enough assets to execute this order.".to_string());
+        }
+
+        // Get relevant orders
+        let book = book_client
+            .load_book(target_faucet_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        let block_num = book.block_num();
+        let existing_orders = book.offers(target_faucet_id);
+
+        // A market order fills any order of the pair it can pay for
+        let (crossing_source_amount, crossing_target_amount) = if self.market {
+            (balance, 0)
+        } else {
+            (source_amount, target_amount)
+        };
+        let crossing_order = Order::new(
+            None,
+            Asset::Fungible(FungibleAsset::new(source_faucet_id, crossing_source_amount).unwrap()),
+            Asset::Fungible(FungibleAsset::new(target_faucet_id, crossing_target_amount).unwrap()),
+        )
+        .with_creator(account_id);
+        let (existing_orders, reclaimed) =
+            self.prevent_self_trade(crossing_order, existing_orders)?;
+
+        // Without a direct market the order is routed through other markets, after confirmation,
+        // so orders executed without prompts are never routed
+        if self.route || (existing_orders.is_empty() && !self.market && self.is_interactive()) {
+            let orders = Self::known_orders(block_num, client)?
+                .into_iter()
+                .filter(|order| {
+                    order.creator() != Some(account_id) && order.can_be_filled_by(account_id)
+                })
+                .collect();
+            let route = find_route(
+                source_faucet_id,
+                target_faucet_id,
+                source_amount,
+                self.max_hops,
+                orders,
+            )
+            .filter(|route| route.amount_out() >= target_amount);
+
+            match route {
+                Some(route) => return Self::execute_route(route, account_id, client).await,
+                None if self.route => return Err("No route can fill the order.".to_string()),
+                None => {}
+            }
+        }
+
+        if self.market {
+            let amount = Self::market_amount(source_amount, target_amount)?;
+            let fills = self.sweep_market(
+                amount,
+                balance,
+                source_faucet_id,
+                target_faucet_id,
+                existing_orders,
+            )?;
+            return Self::fill_success(fills, None, &reclaimed, account_id, true, client)
+                .await
+                .map_err(|e| format!("Failed in fill success: {}", e));
         }
 
         // Build order
         let source_asset =
-            Asset::Fungible(FungibleAsset::new(source_faucet_id, self.source_amount).unwrap());
+            Asset::Fungible(FungibleAsset::new(source_faucet_id, source_amount).unwrap());
         let target_asset =
-            Asset::Fungible(FungibleAsset::new(target_faucet_id, self.target_amount).unwrap());
-        let incoming_order = Order::new(None, source_asset, target_asset);
-
-        // Get relevant notes
-        let tag = build_swap_tag(NoteType::Public, target_faucet_id, source_faucet_id).unwrap();
-        let notes = get_notes_by_tag(&client, tag);
-        let existing_orders: Vec<Order> = notes.into_iter().map(Order::from).collect();
-
-        assert!(
-            !existing_orders.is_empty(),
-            "There are no relevant orders available."
-        );
+            Asset::Fungible(FungibleAsset::new(target_faucet_id, target_amount).unwrap());
+        let time_in_force = self.time_in_force.unwrap_or(TimeInForce::FillOrKill);
+        let expiration = self
+            .expires_in
+            .map(|blocks| block_num.saturating_add(blocks));
+        let note_type = if self.private {
+            NoteType::Private
+        } else {
+            NoteType::Public
+        };
+        let incoming_order = Order::new(None, source_asset, target_asset)
+            .with_time_in_force(time_in_force)
+            .with_expiration(expiration)
+            .with_note_type(note_type);
+        println!("Limit price: {}", incoming_order.price().inverse());
+
+        let interactive = self.is_interactive();
+
+        let time_budget = Duration::from_millis(self.selection_budget_ms);
+        if self.compare_selection {
+            let report = selection_report(incoming_order, existing_orders.clone(), time_budget);
+            print_selection_report(&report);
+        }
 
-        // fill order
-        match Self::fill_order(incoming_order, existing_orders) {
-            Ok(orders) => Self::fill_success(orders, account_id, client)
+        // fill order, an empty book fails the fill and the order is posted or cancelled as its time
+        // in force requires
+        match fill_order(incoming_order, existing_orders, time_budget) {
+            Ok(fills) => {
+                // Good-til-cancelled orders post what could not be filled
+                let remainder = match time_in_force {
+                    TimeInForce::GoodTilCancelled => incoming_order.remainder(&fills),
+                    _ => None,
+                };
+                Self::fill_success(
+                    fills,
+                    remainder,
+                    &reclaimed,
+                    account_id,
+                    interactive,
+                    client,
+                )
                 .await
-                .map_err(|_| "Failed in fill success.".to_string())?,
-            Err(err) => match err {
-                OrderError::FailedFill(order) => Self::fill_failure(order, account_id, client)
+                .map_err(|e| format!("Failed in fill success: {}", e))?
+            }
+            Err(OrderError::FailedFill(order)) => match time_in_force {
+                _ if interactive => Self::fill_failure(order, &reclaimed, account_id, true, client)
                     .await
-                    .map_err(|_| "Failed in fill failure.".to_string())?,
-                _ => panic!("Unknown error."),
+                    .map_err(|e| format!("Failed in fill failure: {}", e))?,
+                TimeInForce::GoodTilCancelled | TimeInForce::PostOnly => {
+                    Self::fill_failure(order, &reclaimed, account_id, false, client)
+                        .await
+                        .map_err(|e| format!("Failed in fill failure: {}", e))?
+                }
+                // Nothing is traded, which `watch` reports as a failed conditional order
+                TimeInForce::ImmediateOrCancel => {
+                    return Err("Nothing could be filled, order cancelled.".to_string())
+                }
+                TimeInForce::FillOrKill => {
+                    return Err("Order cannot be filled entirely, order killed.".to_string())
+                }
             },
+            Err(OrderError::WouldCross) => {
+                return Err("Post-only order would match existing orders.".to_string())
+            }
+            Err(err) => return Err(format!("Failed to fill order: {:?}", err)),
         }
 
         Ok(())
     }
 
-    pub fn fill_order(
-        incoming_order: Order,
+    /// Fills of a market order sweeping the book within the slippage bound, paid from `balance`.
+    fn sweep_market(
+        &self,
+        amount: MarketAmount,
+        balance: u64,
+        source_faucet_id: AccountId,
+        target_faucet_id: AccountId,
         existing_orders: Vec<Order>,
-    ) -> Result<Vec<Order>, OrderError> {
-        // Sort existing orders
-        let sorted_orders = sort_orders(existing_orders);
-
-        // Keep only orders that match incoming order
-        let mut matching_orders = Vec::new();
-        for order in sorted_orders {
-            match match_orders(incoming_order, order) {
-                Ok(order) => matching_orders.push(order),
-                Err(_) => continue,
-            }
+    ) -> Result<Vec<Fill>, String> {
+        let sweep = sweep_orders(
+            source_faucet_id,
+            target_faucet_id,
+            amount,
+            self.max_slippage_bps,
+            existing_orders,
+        )
+        .filter(|sweep| !sweep.fills().is_empty())
+        .ok_or_else(|| "No orders within the slippage bound can fill the order.".to_string())?;
+
+        // Check if user has balance for the swept amount
+        let spent: u64 = sweep.fills().iter().map(Fill::target_amount).sum();
+        if balance < spent {
+            return Err("User does not have enough assets to execute this order.".to_string());
         }
 
-        // The goal is to find the best combination of orders that could fill the incoming order
-        // - Maximize the amount of target asset that the incoming order can get
-        // - Make sure that all swaps can be successfully filled
-        let mut remaining_source = incoming_order.source_asset().unwrap_fungible().amount();
-        let target = incoming_order.target_asset().unwrap_fungible().amount();
+        print_market_sweep(&sweep, self.max_slippage_bps);
+        Ok(sweep.into_fills())
+    }
 
-        let mut final_orders = Vec::new();
-        for order in matching_orders {
-            let order_amount = order.target_asset().unwrap_fungible().amount();
+    /// Applies the self-trade prevention policy to the existing orders `crossing_order` would
+    /// fill, returning the orders left to match and the own resting orders to cancel. These are
+    /// only cancelled by the transaction executing the order, once confirmed.
+    fn prevent_self_trade(
+        &self,
+        crossing_order: Order,
+        existing_orders: Vec<Order>,
+    ) -> Result<(Vec<Order>, Vec<Order>), String> {
+        prevent_self_trade(crossing_order, existing_orders, self.self_trade)
+            .map_err(|_| "Order would fill orders of the same account, order aborted.".to_string())
+    }
 
-            if remaining_source == 0 {
-                break;
-            }
+    /// Active orders of every market the client tracks a tag for.
+    fn known_orders<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        block_num: u32,
+        client: &Client<N, R, S, A>,
+    ) -> Result<Vec<Order>, String> {
+        let tags = client.get_note_tags().map_err(|e| e.to_string())?;
+        let orders = orders_from_notes(
+            tags.into_iter()
+                .flat_map(|tag| get_notes_by_tag(client, tag))
+                .filter(|note| !is_note_consumed(note)),
+        );
 
-            if order_amount <= remaining_source {
-                remaining_source = remaining_source.saturating_sub(order_amount);
-                final_orders.push(order);
-            }
-        }
+        Ok(active_orders(orders, block_num))
+    }
 
-        let final_target_amount: u64 = final_orders
-            .iter()
-            .map(|order| order.source_asset().unwrap_fungible().amount())
-            .sum();
+    async fn execute_route<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        route: Route,
+        account_id: AccountId,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        print_route(&route);
 
-        // We have not hit the required target amount
-        if final_target_amount < target {
-            return Err(OrderError::FailedFill(incoming_order));
+        if !Self::confirm("Do you want to proceed with the execution?")
+            .map_err(|e| format!("{:?}", e))?
+        {
+            println!("Execution cancelled by user.");
+            return Ok(());
         }
 
-        Ok(final_orders)
+        // Legs are consumed in order within one transaction, so the asset received in a leg pays
+        // the next one
+        let fills: Vec<Fill> = route.fills().copied().collect();
+        OrderBookClient::new(client)
+            .execute_fills(account_id, &fills, None)
+            .await
+            .map_err(|e| format!("Failed to execute route: {}", e))?;
+
+        println!("Order filled successfully.");
+        Ok(())
     }
 
-    async fn fill_success<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
-        orders: Vec<Order>,
+    /// Without an explicit time in force the user is prompted before filling or posting a limit
+    /// order. Orders with one run without prompts, e.g. when submitted by `watch`.
+    fn is_interactive(&self) -> bool {
+        self.time_in_force.is_none()
+    }
+
+    /// Market orders fix either the amount to spend or the amount to receive.
+    fn market_amount(source_amount: u64, target_amount: u64) -> Result<MarketAmount, String> {
+        match (source_amount, target_amount) {
+            (0, 0) => Err("Market orders require a source or a target amount.".to_string()),
+            (source_amount, 0) => Ok(MarketAmount::Spend(source_amount)),
+            (0, target_amount) => Ok(MarketAmount::Receive(target_amount)),
+            _ => Err(
+                "Market orders take either a source or a target amount, set the other one to 0."
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Previews `fills`, the posted `remainder` and the own orders of `reclaimed` cancelled along,
+    /// checks them with a local execution and submits them, after confirmation if `interactive`.
+    pub async fn fill_success<
+        N: NodeRpcClient,
+        R: FeltRng,
+        S: Store,
+        A: TransactionAuthenticator,
+    >(
+        fills: Vec<Fill>,
+        remainder: Option<Order>,
+        reclaimed: &[Order],
         account_id: AccountId,
+        interactive: bool,
         client: &mut Client<N, R, S, A>,
-    ) -> Result<(), OrderError> {
+    ) -> Result<(), OrderBookError> {
         // print final orders
+        let orders: Vec<Order> = fills.iter().map(Fill::order).collect();
         print_order_table("Final orders:", &orders);
 
         // print user balance update
-        print_balance_update(&orders);
+        print_balance_update(&fills);
 
-        // Prompt user for confirmation
-        print!("Do you want to proceed with the execution? [Y/n]: ");
-        io::stdout()
-            .flush()
-            .map_err(|e| OrderError::InternalError(format!("Failed to flush stdout: {}", e)))?;
+        if let Some(remainder) = remainder {
+            print_order_table("Remaining order added to the order book:", &[remainder]);
+        }
+        if !reclaimed.is_empty() {
+            print_order_table("Own resting orders to cancel:", reclaimed);
+        }
 
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .map_err(|e| OrderError::InternalError(format!("Failed to read user input: {}", e)))?;
+        // Notes are only consumed if their script is trusted and the transaction simulated
+        // locally changes the vault exactly as previewed
+        let mut book_client = OrderBookClient::new(client);
+        let transaction =
+            book_client.verify_fills_reclaiming(account_id, &fills, remainder, reclaimed)?;
+        println!("Simulated execution matches the balance update preview.");
 
-        let proceed = input.trim().to_lowercase();
-        if proceed != "y" && proceed != "yes" && !proceed.is_empty() {
+        // Prompt user for confirmation
+        if interactive && !Self::confirm("Do you want to proceed with the execution?")? {
             println!("Execution cancelled by user.");
             return Ok(());
         }
 
         // Proceed with execution
-        let final_order_ids = orders
-            .into_iter()
-            .map(|order| order.id().ok_or(OrderError::MissingId))
-            .collect::<Result<Vec<NoteId>, OrderError>>()?;
-
-        // Create transaction
-        let transaction_request = TransactionRequest::consume_notes(final_order_ids);
-        let transaction = client
-            .new_transaction(account_id, transaction_request)
-            .map_err(|e| {
-                OrderError::InternalError(format!("Failed to create transaction: {}", e))
-            })?;
-
-        client.submit_transaction(transaction).await.map_err(|e| {
-            OrderError::InternalError(format!("Failed to submit transaction: {}", e))
-        })?;
+        let private_notes = Self::private_notes(&transaction);
+        book_client.submit(transaction).await?;
 
         println!("Order filled successfully.");
+        Self::print_private_notes(&private_notes);
         Ok(())
     }
 
     async fn fill_failure<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
         order: Order,
+        reclaimed: &[Order],
         account_id: AccountId,
+        interactive: bool,
         client: &mut Client<N, R, S, A>,
-    ) -> Result<(), OrderError> {
+    ) -> Result<(), OrderBookError> {
         println!("Unable to fill the requested order.");
+        if !reclaimed.is_empty() {
+            print_order_table("Own resting orders to cancel:", reclaimed);
+        }
 
         // Prompt user for confirmation
-        print!("Do you want to add order to the order book? [Y/n]: ");
+        if interactive && !Self::confirm("Do you want to add order to the order book?")? {
+            println!("Execution cancelled by user.");
+            return Ok(());
+        }
+
+        let mut book_client = OrderBookClient::new(client);
+        let transaction =
+            book_client.verify_fills_reclaiming(account_id, &[], Some(order), reclaimed)?;
+        let private_notes = Self::private_notes(&transaction);
+        book_client.submit(transaction).await?;
+
+        println!("Failed to fill order: {:?}", order);
+        Self::print_private_notes(&private_notes);
+
+        Ok(())
+    }
+
+    /// Private notes created by `transaction`, only known to this client until exported.
+    pub fn private_notes(transaction: &TransactionResult) -> Vec<NoteId> {
+        transaction
+            .created_notes()
+            .iter()
+            .filter(|note| note.metadata().note_type() == NoteType::Private)
+            .map(|note| note.id())
+            .collect()
+    }
+
+    pub fn print_private_notes(note_ids: &[NoteId]) {
+        for note_id in note_ids {
+            println!(
+                "Private order posted in note {}, share it with `export {} --to <counterparty>`.",
+                note_id.to_hex(),
+                note_id.to_hex()
+            );
+        }
+    }
+
+    /// Prompts the user with a yes/no question, answering yes by default.
+    pub fn confirm(question: &str) -> Result<bool, OrderError> {
+        print!("{} [Y/n]: ", question);
         io::stdout()
             .flush()
             .map_err(|e| OrderError::InternalError(format!("Failed to flush stdout: {}", e)))?;
@@ -209,28 +521,6 @@ impl OrderCmd {
             .map_err(|e| OrderError::InternalError(format!("Failed to read user input: {}", e)))?;
 
         let proceed = input.trim().to_lowercase();
-        if proceed != "y" && proceed != "yes" && !proceed.is_empty() {
-            println!("Execution cancelled by user.");
-            return Ok(());
-        }
-
-        let swap_data =
-            SwapTransactionData::new(account_id, order.source_asset(), order.target_asset());
-        let transaction_request =
-            TransactionRequest::swap(swap_data, NoteType::Public, client.rng()).unwrap();
-
-        let transaction = client
-            .new_transaction(account_id, transaction_request)
-            .map_err(|e| {
-                OrderError::InternalError(format!("Failed to create transaction: {}", e))
-            })?;
-
-        client.submit_transaction(transaction).await.map_err(|e| {
-            OrderError::InternalError(format!("Failed to submit transaction: {}", e))
-        })?;
-
-        println!("Failed to fill order: {:?}", order);
-
-        Ok(())
+        Ok(proceed == "y" || proceed == "yes" || proceed.is_empty())
     }
 }
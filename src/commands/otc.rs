@@ -0,0 +1,133 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    notes::{NoteId, NoteType},
+    rpc::NodeRpcClient,
+    store::Store,
+    Client,
+};
+
+use super::order::OrderCmd;
+use miden_order_book::{client::OrderBookClient, order::Order, utils::print_order_table};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Post an OTC order that only the given counterparty can fill")]
+pub struct OtcCmd {
+    /// Account posting the order
+    pub user: String,
+
+    /// Only account allowed to fill the order
+    pub counterparty: String,
+
+    /// Target faucet id
+    pub target_faucet: String,
+
+    /// Target asset amount
+    pub target_amount: u64,
+
+    /// Source faucet id
+    pub source_faucet: String,
+
+    /// Source asset amount
+    pub source_amount: u64,
+
+    /// Number of blocks after which the order expires
+    #[arg(long)]
+    pub expires_in: Option<u32>,
+
+    /// Post the order in a private note, to be exported to the counterparty
+    #[arg(long)]
+    pub private: bool,
+}
+
+impl OtcCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let counterparty =
+            AccountId::from_hex(self.counterparty.as_str()).map_err(|e| e.to_string())?;
+        let source_faucet_id =
+            AccountId::from_hex(self.source_faucet.as_str()).map_err(|e| e.to_string())?;
+        let target_faucet_id =
+            AccountId::from_hex(self.target_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        if counterparty == account_id {
+            return Err("The counterparty must be another account.".to_string());
+        }
+
+        // Check if user has balance
+        let balance = OrderBookClient::new(client)
+            .balance(account_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        if balance < self.source_amount {
+            return Err("User does not have enough assets to post this order.".to_string());
+        }
+
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+        let source_asset =
+            FungibleAsset::new(source_faucet_id, self.source_amount).map_err(|e| e.to_string())?;
+        let target_asset =
+            FungibleAsset::new(target_faucet_id, self.target_amount).map_err(|e| e.to_string())?;
+        let note_type = if self.private {
+            NoteType::Private
+        } else {
+            NoteType::Public
+        };
+        let order = Order::new(
+            None,
+            Asset::Fungible(source_asset),
+            Asset::Fungible(target_asset),
+        )
+        .with_creator(account_id)
+        .with_expiration(
+            self.expires_in
+                .map(|blocks| block_num.saturating_add(blocks)),
+        )
+        .with_note_type(note_type)
+        .with_counterparty(Some(counterparty));
+
+        let title = format!("OTC order for {}:", counterparty);
+        print_order_table(title.as_str(), &[order]);
+        if !OrderCmd::confirm("Do you want to send the order to the counterparty?")
+            .map_err(|e| format!("{:?}", e))?
+        {
+            println!("Execution cancelled by user.");
+            return Ok(());
+        }
+
+        // The order is not matched against the book, it waits for the counterparty. Its note is
+        // tagged for the counterparty and kept as an output note, from which it is cancelled
+        let mut book_client = OrderBookClient::new(client);
+        let transaction = book_client
+            .verify_fills(account_id, &[], Some(order))
+            .map_err(|e| e.to_string())?;
+        let note_ids: Vec<NoteId> = transaction
+            .created_notes()
+            .iter()
+            .map(|note| note.id())
+            .collect();
+        book_client
+            .submit(transaction)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if self.private {
+            OrderCmd::print_private_notes(&note_ids);
+        } else {
+            for note_id in note_ids {
+                println!(
+                    "OTC order posted in note {}, {} can list it with `offers`.",
+                    note_id.to_hex(),
+                    counterparty
+                );
+            }
+        }
+        Ok(())
+    }
+}
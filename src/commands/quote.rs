@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use clap::Parser;
+use serde::Serialize;
+
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+    auth::TransactionAuthenticator,
+    crypto::FeltRng,
+    rpc::NodeRpcClient,
+    store::Store,
+    Client,
+};
+
+use miden_order_book::{
+    client::OrderBookClient,
+    constants::DEFAULT_SELECTION_BUDGET_MS,
+    errors::{OrderBookError, OrderError},
+    order::{Fill, Order, Quote, TimeInForce},
+    price::Price,
+    utils::print_quote,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Preview an order and execute it locally, without submitting it")]
+pub struct QuoteCmd {
+    /// Account executing the order
+    pub user: String,
+
+    /// Target faucet id
+    pub target_faucet: String,
+
+    /// Target asset amount
+    pub target_amount: u64,
+
+    /// Source faucet id
+    pub source_faucet: String,
+
+    /// Source asset amount
+    pub source_amount: u64,
+
+    /// Time in force of the quoted order
+    #[arg(long, value_enum, default_value = "ioc")]
+    pub time_in_force: TimeInForce,
+
+    /// Time budget in milliseconds for the optimal order selection
+    #[arg(long, default_value_t = DEFAULT_SELECTION_BUDGET_MS)]
+    pub selection_budget_ms: u64,
+
+    /// Print the quote as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Quote as printed with `--json`. Prices are amounts paid per unit received.
+#[derive(Debug, Serialize)]
+pub struct QuoteReport {
+    pub account_id: String,
+    pub source_faucet_id: String,
+    pub target_faucet_id: String,
+    pub source_amount: u64,
+    pub target_amount: u64,
+    pub notes: Vec<QuotedNote>,
+    pub spent: u64,
+    pub received: u64,
+    pub leftover_source_amount: u64,
+    pub best_price: Option<String>,
+    pub average_price: Option<String>,
+    pub worst_price: Option<String>,
+    pub price_impact_bps: Option<i64>,
+    /// Whether the transaction was executed locally without error.
+    pub executed: bool,
+    pub execution_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuotedNote {
+    pub note_id: String,
+    pub price: String,
+    pub paid: u64,
+    pub received: u64,
+    pub partial: bool,
+}
+
+impl QuoteCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let source_faucet_id =
+            AccountId::from_hex(self.source_faucet.as_str()).map_err(|e| e.to_string())?;
+        let target_faucet_id =
+            AccountId::from_hex(self.target_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        let source_asset =
+            FungibleAsset::new(source_faucet_id, self.source_amount).map_err(|e| e.to_string())?;
+        let target_asset =
+            FungibleAsset::new(target_faucet_id, self.target_amount).map_err(|e| e.to_string())?;
+        let incoming_order = Order::new(
+            None,
+            Asset::Fungible(source_asset),
+            Asset::Fungible(target_asset),
+        )
+        .with_time_in_force(self.time_in_force);
+
+        // Own notes are never filled, and nothing is submitted
+        let mut book_client = OrderBookClient::new(client);
+        let time_budget = Duration::from_millis(self.selection_budget_ms);
+        let quote = book_client
+            .quote(account_id, incoming_order, time_budget)
+            .map_err(|e| match e {
+                OrderBookError::Order(OrderError::WouldCross) => {
+                    "Post-only order would match existing orders.".to_string()
+                }
+                e => format!("Failed to quote order: {}", e),
+            })?;
+        let execution_error = book_client
+            .simulate(account_id, incoming_order, &quote)
+            .err()
+            .map(|e| e.to_string());
+
+        let report = self.report(account_id, &quote, execution_error);
+        if self.json {
+            let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+            println!("{}", json);
+        } else {
+            print_quote(&quote, report.execution_error.as_deref());
+        }
+
+        Ok(())
+    }
+
+    fn report(
+        &self,
+        account_id: AccountId,
+        quote: &Quote,
+        execution_error: Option<String>,
+    ) -> QuoteReport {
+        let price = |price: Option<Price>| price.map(|price| price.to_string());
+        QuoteReport {
+            account_id: account_id.to_string(),
+            source_faucet_id: self.source_faucet.clone(),
+            target_faucet_id: self.target_faucet.clone(),
+            source_amount: self.source_amount,
+            target_amount: self.target_amount,
+            notes: quote.fills().iter().map(QuotedNote::from).collect(),
+            spent: quote.spent(),
+            received: quote.received(),
+            leftover_source_amount: quote.leftover(),
+            best_price: price(quote.best_price()),
+            average_price: price(quote.average_price()),
+            worst_price: price(quote.worst_price()),
+            price_impact_bps: quote.price_impact_bps(),
+            executed: execution_error.is_none(),
+            execution_error,
+        }
+    }
+}
+
+impl From<&Fill> for QuotedNote {
+    fn from(fill: &Fill) -> Self {
+        QuotedNote {
+            note_id: fill
+                .order()
+                .id()
+                .map_or("N/A".to_string(), |id| id.to_hex()),
+            price: fill.order().price().to_string(),
+            paid: fill.target_amount(),
+            received: fill.source_amount(),
+            partial: fill.is_partial(),
+        }
+    }
+}
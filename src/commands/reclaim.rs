@@ -0,0 +1,57 @@
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient,
+    store::Store, Client,
+};
+
+use miden_order_book::{
+    order::Order,
+    utils::{
+        get_notes_by_tag, is_note_consumed, orders_from_notes, print_order_table, reclaim_orders,
+    },
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Reclaim the expired orders created by an account")]
+pub struct ReclaimCmd {
+    /// Account that created the orders
+    pub user: String,
+
+    /// Tags of the order books to reclaim orders from
+    #[clap(required = true)]
+    pub tags: Vec<u32>,
+}
+
+impl ReclaimCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+
+        // Only SWAPp notes can be consumed back by their creator
+        let expired_orders: Vec<Order> = orders_from_notes(
+            self.tags
+                .iter()
+                .flat_map(|tag| get_notes_by_tag(client, (*tag).into()))
+                .filter(|note| !is_note_consumed(note)),
+        )
+        .into_iter()
+        .filter(|order| order.can_be_reclaimed_by(account_id) && order.is_expired(block_num))
+        .collect();
+
+        if expired_orders.is_empty() {
+            println!("No expired orders to reclaim.");
+            return Ok(());
+        }
+
+        print_order_table("Expired orders to reclaim:", &expired_orders);
+
+        reclaim_orders(client, account_id, &expired_orders).await?;
+
+        println!("Reclaimed {} expired orders.", expired_orders.len());
+        Ok(())
+    }
+}
@@ -21,7 +21,7 @@ use miden_client::{
 use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 
-use crate::{
+use miden_order_book::{
     constants::{CLOB_DATA_FILE_PATH, DB_FILE_PATH},
     utils::{clear_notes_tables, create_swap_notes_transaction_request},
 };
@@ -0,0 +1,28 @@
+use clap::Parser;
+
+use miden_order_book::{sharing::SharedOrderBook, utils::print_shared_orders};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "List the order notes exported to or imported from counterparties")]
+pub struct SharedCmd {
+    /// Only list the notes exchanged with this counterparty
+    #[arg(long)]
+    pub with: Option<String>,
+}
+
+impl SharedCmd {
+    pub fn execute(&self) -> Result<(), String> {
+        let shares: Vec<_> = SharedOrderBook::load()?
+            .shares
+            .into_iter()
+            .filter(|share| {
+                self.with
+                    .as_ref()
+                    .map_or(true, |counterparty| *counterparty == share.counterparty)
+            })
+            .collect();
+        print_shared_orders("Shared orders:", &shares);
+
+        Ok(())
+    }
+}
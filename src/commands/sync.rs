@@ -4,6 +4,9 @@ use miden_client::{
     auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient, store::Store, Client,
 };
 
+use super::iceberg::IcebergCmd;
+use miden_order_book::client::OrderBookClient;
+
 #[derive(Debug, Clone, Parser)]
 #[clap(about = "Sync rollup state")]
 pub struct SyncCmd {}
@@ -13,8 +16,13 @@ impl SyncCmd {
         &self,
         client: &mut Client<N, R, S, A>,
     ) -> Result<(), String> {
-        client.sync_state().await?;
+        OrderBookClient::new(client)
+            .sync()
+            .await
+            .map_err(|e| e.to_string())?;
         println!("Sync successful.");
-        Ok(())
+
+        // Post the next slice of the icebergs whose resting slice has been filled
+        IcebergCmd::replenish(client).await
     }
 }
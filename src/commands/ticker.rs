@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use clap::Parser;
+use miden_client::{
+    accounts::AccountId, auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient,
+    store::Store, Client,
+};
+use tokio::time::sleep;
+
+use super::setup::SetupCmd;
+use miden_order_book::{
+    client::OrderBookClient,
+    constants::DEFAULT_WATCH_INTERVAL_SECS,
+    order::Order,
+    utils::{get_notes_by_tag, print_ticker},
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Summarize the top of book, liquidity and volume of every known market")]
+pub struct TickerCmd {
+    /// Count the volume traded from this block on
+    #[arg(long, default_value_t = 0)]
+    pub since_block: u32,
+
+    /// Sync and print the summary again every interval
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Seconds to wait between two syncs when watching
+    #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL_SECS, requires = "watch")]
+    pub interval: u64,
+}
+
+impl TickerCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        loop {
+            let block_num = if self.watch {
+                OrderBookClient::new(client)
+                    .sync()
+                    .await
+                    .map_err(|e| e.to_string())?
+            } else {
+                client.get_sync_height().map_err(|e| e.to_string())?
+            };
+
+            let markets = Self::markets(client)?;
+            let book_client = OrderBookClient::new(client);
+            let summaries = markets
+                .into_iter()
+                .map(|(base_faucet_id, quote_faucet_id)| {
+                    book_client.summary(base_faucet_id, quote_faucet_id, self.since_block)
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+
+            let title = format!(
+                "Markets at block {}, prices in quote per base, volume since block {}:",
+                block_num, self.since_block
+            );
+            print_ticker(title.as_str(), &summaries);
+
+            if !self.watch {
+                return Ok(());
+            }
+            sleep(Duration::from_secs(self.interval)).await;
+        }
+    }
+
+    /// Markets of the CLOB setup and of the swap notes of every tracked tag. The base asset of a
+    /// market is the one offered under the first tag listing it.
+    fn markets<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        client: &Client<N, R, S, A>,
+    ) -> Result<Vec<(AccountId, AccountId)>, String> {
+        let mut markets: Vec<(AccountId, AccountId)> = Vec::new();
+        if let Ok(clob) = SetupCmd::import_clob_data() {
+            markets.push((clob.faucet1, clob.faucet2));
+        }
+
+        let tags = client.get_note_tags().map_err(|e| e.to_string())?;
+        for order in tags
+            .into_iter()
+            .flat_map(|tag| get_notes_by_tag(client, tag))
+            .filter_map(|note| Order::try_from(&note).ok())
+        {
+            let offered = order.source_asset().faucet_id();
+            let requested = order.target_asset().faucet_id();
+            if !markets
+                .iter()
+                .any(|market| *market == (offered, requested) || *market == (requested, offered))
+            {
+                markets.push((offered, requested));
+            }
+        }
+
+        Ok(markets)
+    }
+}
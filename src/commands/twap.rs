@@ -0,0 +1,243 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+
+use miden_client::{
+    accounts::AccountId, assets::FungibleAsset, auth::TransactionAuthenticator, crypto::FeltRng,
+    notes::NoteType, rpc::NodeRpcClient, store::Store, transactions::build_swap_tag, Client,
+};
+
+use miden_order_book::{
+    client::OrderBookClient,
+    conditional::best_price,
+    constants::DEFAULT_SELECTION_BUDGET_MS,
+    errors::OrderError,
+    order::{fill_order, prevent_self_trade, Fill, Order, SelfTradePrevention},
+    twap::{Twap, TwapBook, TwapInterval, UnfilledChild},
+    utils::print_twaps,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Split an order into child orders executed at a regular interval")]
+pub struct TwapCmd {
+    /// Account executing the order
+    pub user: String,
+
+    /// Target faucet id
+    pub target_faucet: String,
+
+    /// Target asset amount, setting the limit price of every child order
+    pub target_amount: u64,
+
+    /// Source faucet id
+    pub source_faucet: String,
+
+    /// Source asset amount
+    pub source_amount: u64,
+
+    /// Execute a child order every this many blocks
+    #[arg(
+        long,
+        required_unless_present = "every_secs",
+        conflicts_with = "every_secs"
+    )]
+    pub every_blocks: Option<u32>,
+
+    /// Execute a child order every this many seconds
+    #[arg(long)]
+    pub every_secs: Option<u64>,
+
+    /// Time over which the order is executed, in the unit of the interval
+    #[arg(long)]
+    pub window: u64,
+
+    /// What happens to the part of a child order that cannot be filled
+    #[arg(long, value_enum, default_value = "skip")]
+    pub on_unfilled: UnfilledChild,
+}
+
+impl TwapCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let account_id = AccountId::from_hex(self.user.as_str()).map_err(|e| e.to_string())?;
+        let source_faucet_id =
+            AccountId::from_hex(self.source_faucet.as_str()).map_err(|e| e.to_string())?;
+        let target_faucet_id =
+            AccountId::from_hex(self.target_faucet.as_str()).map_err(|e| e.to_string())?;
+
+        let (interval, every) = match (self.every_blocks, self.every_secs) {
+            (Some(blocks), None) => (TwapInterval::Blocks(blocks), blocks as u64),
+            (None, Some(seconds)) => (TwapInterval::Seconds(seconds), seconds),
+            _ => return Err("Set either a block or a seconds interval.".to_string()),
+        };
+        let children = u32::try_from(self.window / every.max(1)).unwrap_or(u32::MAX);
+        if every == 0 || children == 0 || children as u64 > self.source_amount {
+            return Err(
+                "The window must hold at least one interval, and each child order must offer \
+                 something."
+                    .to_string(),
+            );
+        }
+
+        // Check if user has balance for the whole order
+        let (account, _) = client.get_account(account_id).map_err(|e| e.to_string())?;
+        if account
+            .vault()
+            .get_balance(source_faucet_id)
+            .map_err(|e| e.to_string())?
+            < self.source_amount
+        {
+            return Err("User does not have enough assets to execute this order.".to_string());
+        }
+
+        let source_asset =
+            FungibleAsset::new(source_faucet_id, self.source_amount).map_err(|e| e.to_string())?;
+        let target_asset =
+            FungibleAsset::new(target_faucet_id, self.target_amount).map_err(|e| e.to_string())?;
+
+        // Track the tag of the orders the children fill against, so they are synced
+        let tag = build_swap_tag(NoteType::Public, target_faucet_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        client.add_note_tag(tag).map_err(|e| e.to_string())?;
+
+        let existing_orders = Self::existing_orders(source_faucet_id, target_faucet_id, client)?;
+        let arrival_price = best_price(source_faucet_id, target_faucet_id, &existing_orders);
+
+        let mut book = TwapBook::load()?;
+        let id = book.add(
+            account_id,
+            source_asset,
+            target_asset,
+            children,
+            interval,
+            self.on_unfilled,
+        );
+        book.get_mut(id).expect("TWAP was just added").arrival_price = arrival_price;
+        book.save()?;
+
+        // The first child order is due immediately
+        Self::run_due(client).await?;
+        println!("Run `watch` to execute the next child orders.");
+        Ok(())
+    }
+
+    /// Executes the next child order of every running TWAP that is due, printing the summary of
+    /// the TWAPs completed. A TWAP whose child order fails is marked as failed without stopping
+    /// the other ones.
+    pub async fn run_due<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let mut book = TwapBook::load()?;
+
+        let mut completed = Vec::new();
+        let mut failed = Vec::new();
+        for twap in book
+            .twaps
+            .iter_mut()
+            .filter(|twap| twap.is_due(block_num, timestamp))
+        {
+            if let Err(err) = Self::run_child(twap, block_num, timestamp, client).await {
+                println!("TWAP {} failed: {}", twap.id, err);
+                twap.fail();
+                failed.push(twap.clone());
+            } else if !twap.is_running() {
+                completed.push(twap.clone());
+            }
+        }
+
+        book.save()?;
+        if !completed.is_empty() {
+            print_twaps("TWAP orders completed:", &completed);
+        }
+        if !failed.is_empty() {
+            print_twaps("TWAP orders failed:", &failed);
+        }
+        Ok(())
+    }
+
+    async fn run_child<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        twap: &mut Twap,
+        block_num: u32,
+        timestamp: u64,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let Some(child) = twap.next_child() else {
+            return Ok(());
+        };
+        let child_amount = child.source_asset().unwrap_fungible().amount();
+
+        let (account, _) = client
+            .get_account(twap.account_id)
+            .map_err(|e| e.to_string())?;
+        if account
+            .vault()
+            .get_balance(twap.source_faucet_id)
+            .map_err(|e| e.to_string())?
+            < child_amount
+        {
+            return Err(format!(
+                "User does not have enough assets to execute the child order of TWAP {}.",
+                twap.id
+            ));
+        }
+
+        let existing_orders =
+            Self::existing_orders(twap.source_faucet_id, twap.target_faucet_id, client)?;
+        // Child orders never fill orders of the same account
+        let (existing_orders, _) = prevent_self_trade(
+            child.with_creator(twap.account_id),
+            existing_orders,
+            SelfTradePrevention::Skip,
+        )
+        .map_err(|e| format!("Failed to fill child order: {:?}", e))?;
+
+        let time_budget = Duration::from_millis(DEFAULT_SELECTION_BUDGET_MS);
+        let fills = match fill_order(child, existing_orders, time_budget) {
+            Ok(fills) => fills,
+            Err(OrderError::FailedFill(_)) => Vec::new(),
+            Err(err) => return Err(format!("Failed to fill child order: {:?}", err)),
+        };
+
+        let posted = match twap.on_unfilled {
+            UnfilledChild::Post => child.remainder(&fills),
+            UnfilledChild::Skip | UnfilledChild::CarryOver => None,
+        };
+        if !fills.is_empty() || posted.is_some() {
+            OrderBookClient::new(client)
+                .execute_fills(twap.account_id, &fills, posted)
+                .await
+                .map_err(|e| format!("Failed to execute child order: {}", e))?;
+        }
+
+        twap.record_child(&child, &fills, posted.as_ref(), block_num, timestamp);
+        println!(
+            "TWAP {} child {} of {}: paid {} of {}, received {}.",
+            twap.id,
+            twap.executed_children,
+            twap.children,
+            fills.iter().map(Fill::target_amount).sum::<u64>(),
+            child_amount,
+            fills.iter().map(Fill::source_amount).sum::<u64>()
+        );
+        Ok(())
+    }
+
+    /// Active orders offering `target_faucet_id` for `source_faucet_id`.
+    fn existing_orders<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        source_faucet_id: AccountId,
+        target_faucet_id: AccountId,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<Vec<Order>, String> {
+        let book = OrderBookClient::new(client)
+            .load_book(target_faucet_id, source_faucet_id)
+            .map_err(|e| e.to_string())?;
+        Ok(book.offers(target_faucet_id))
+    }
+}
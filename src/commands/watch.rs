@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use clap::Parser;
+
+use miden_client::{
+    auth::TransactionAuthenticator, crypto::FeltRng, rpc::NodeRpcClient, store::Store, Client,
+};
+use tokio::time::sleep;
+
+use super::{order::OrderCmd, sync::SyncCmd, twap::TwapCmd};
+use miden_order_book::{
+    client::OrderBookClient,
+    conditional::{best_price, ConditionalBook, ConditionalOrder},
+    constants::{
+        DEFAULT_MAX_HOPS, DEFAULT_MAX_SLIPPAGE_BPS, DEFAULT_SELECTION_BUDGET_MS,
+        DEFAULT_WATCH_INTERVAL_SECS,
+    },
+    order::SelfTradePrevention,
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Sync continuously, submitting triggered conditional orders and due TWAP children")]
+pub struct WatchCmd {
+    /// Seconds to wait between two syncs
+    #[arg(long, default_value_t = DEFAULT_WATCH_INTERVAL_SECS)]
+    pub interval: u64,
+
+    /// Sync and check the scheduled orders once, then exit
+    #[arg(long)]
+    pub once: bool,
+}
+
+impl WatchCmd {
+    pub async fn execute<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        &self,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        loop {
+            SyncCmd {}.execute(client).await?;
+            Self::check_conditional_orders(client).await?;
+            // TWAP failures are recorded on the TWAPs, the watcher keeps running
+            if let Err(err) = TwapCmd::run_due(client).await {
+                println!("Failed to run the due TWAP orders: {}", err);
+            }
+
+            if self.once {
+                return Ok(());
+            }
+            sleep(Duration::from_secs(self.interval)).await;
+        }
+    }
+
+    /// Submits every pending conditional order whose trigger price is crossed by the best price
+    /// of its market.
+    async fn check_conditional_orders<
+        N: NodeRpcClient,
+        R: FeltRng,
+        S: Store,
+        A: TransactionAuthenticator,
+    >(
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        let block_num = client.get_sync_height().map_err(|e| e.to_string())?;
+        let mut book = ConditionalBook::load()?;
+
+        for index in 0..book.orders.len() {
+            let order = &book.orders[index];
+            if !order.is_pending() {
+                continue;
+            }
+
+            // Top of the book among the orders the conditional order would fill against
+            let existing_orders: Vec<_> = OrderBookClient::new(client)
+                .load_book(order.target_faucet_id, order.source_faucet_id)
+                .map_err(|e| e.to_string())?
+                .offers(order.target_faucet_id)
+                .into_iter()
+                // Own orders are skipped by the order once submitted
+                .filter(|existing| existing.creator() != Some(order.account_id))
+                .collect();
+            let Some(price) = best_price(
+                order.source_faucet_id,
+                order.target_faucet_id,
+                &existing_orders,
+            ) else {
+                continue;
+            };
+            if !order.is_triggered(price) {
+                continue;
+            }
+
+            println!(
+                "Conditional order {} triggered at price {}.",
+                order.id, price
+            );
+
+            // Persisted before submitting so a failure never submits the order twice
+            let order_cmd = Self::order_cmd(order);
+            book.orders[index].trigger(block_num, true);
+            book.save()?;
+
+            if let Err(err) = Self::submit(order_cmd, &book.orders[index], client).await {
+                println!(
+                    "Conditional order {} failed: {}",
+                    book.orders[index].id, err
+                );
+                book.orders[index].trigger(block_num, false);
+                book.save()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn submit<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+        order_cmd: OrderCmd,
+        order: &ConditionalOrder,
+        client: &mut Client<N, R, S, A>,
+    ) -> Result<(), String> {
+        // The order command refuses orders the account cannot pay for
+        let (account, _) = client
+            .get_account(order.account_id)
+            .map_err(|e| e.to_string())?;
+        if account
+            .vault()
+            .get_balance(order.source_faucet_id)
+            .map_err(|e| e.to_string())?
+            < order.source_amount
+        {
+            return Err("User does not have enough assets to execute this order.".to_string());
+        }
+
+        order_cmd.execute(client).await
+    }
+
+    /// Order command submitting `order` without prompts, as if run from the command line. It has a
+    /// time in force, so it is neither routed nor confirmed.
+    fn order_cmd(order: &ConditionalOrder) -> OrderCmd {
+        OrderCmd {
+            action: None,
+            user: Some(order.account_id.to_string()),
+            target_faucet: Some(order.target_faucet_id.to_string()),
+            target_amount: Some(order.target_amount),
+            source_faucet: Some(order.source_faucet_id.to_string()),
+            source_amount: Some(order.source_amount),
+            selection_budget_ms: DEFAULT_SELECTION_BUDGET_MS,
+            compare_selection: false,
+            market: false,
+            max_slippage_bps: DEFAULT_MAX_SLIPPAGE_BPS,
+            time_in_force: Some(order.time_in_force),
+            expires_in: None,
+            route: false,
+            max_hops: DEFAULT_MAX_HOPS,
+            self_trade: SelfTradePrevention::Skip,
+            private: false,
+        }
+    }
+}
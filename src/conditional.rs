@@ -0,0 +1,215 @@
+use miden_client::{accounts::AccountId, assets::FungibleAsset};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::CONDITIONAL_ORDERS_FILE_PATH,
+    order::{Order, TimeInForce},
+    price::Price,
+    utils::{load_toml, save_toml},
+};
+
+// Conditional order
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionalKind {
+    /// Triggered once the best price falls to the trigger price.
+    StopLoss,
+    /// Triggered once the best price rises to the trigger price.
+    TakeProfit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionalStatus {
+    /// Waiting for the trigger price to be crossed.
+    Pending,
+    /// The trigger price was crossed and the order was submitted.
+    Triggered,
+    /// The trigger price was crossed but the order could not be submitted.
+    Failed,
+    /// Cancelled by its creator before being triggered.
+    Cancelled,
+}
+
+/// An order kept locally until the best price of its market crosses a trigger price, at which
+/// point it is submitted like any other order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConditionalOrder {
+    pub id: u32,
+    pub account_id: AccountId,
+    pub source_faucet_id: AccountId,
+    pub target_faucet_id: AccountId,
+    pub source_amount: u64,
+    pub target_amount: u64,
+    pub kind: ConditionalKind,
+    /// Amount of the target asset received per unit of the source asset.
+    pub trigger_price: Price,
+    pub time_in_force: TimeInForce,
+    /// Block at which the trigger price was crossed.
+    pub triggered_at: Option<u32>,
+    pub status: ConditionalStatus,
+}
+
+impl ConditionalOrder {
+    pub fn is_pending(&self) -> bool {
+        self.status == ConditionalStatus::Pending
+    }
+
+    /// Whether `best_price`, the amount of the target asset received per unit of the source
+    /// asset at the top of the book, crosses the trigger price.
+    pub fn is_triggered(&self, best_price: Price) -> bool {
+        match self.kind {
+            ConditionalKind::StopLoss => best_price <= self.trigger_price,
+            ConditionalKind::TakeProfit => best_price >= self.trigger_price,
+        }
+    }
+
+    /// Records the order as submitted, or failed, at `block_num`.
+    pub fn trigger(&mut self, block_num: u32, submitted: bool) {
+        self.triggered_at = Some(block_num);
+        self.status = if submitted {
+            ConditionalStatus::Triggered
+        } else {
+            ConditionalStatus::Failed
+        };
+    }
+}
+
+/// Best amount of `target_faucet_id` received per unit of `source_faucet_id` among `orders`, or
+/// `None` if no order offers it.
+pub fn best_price(
+    source_faucet_id: AccountId,
+    target_faucet_id: AccountId,
+    orders: &[Order],
+) -> Option<Price> {
+    orders
+        .iter()
+        .filter(|order| {
+            order.source_asset().faucet_id() == target_faucet_id
+                && order.target_asset().faucet_id() == source_faucet_id
+        })
+        .map(|order| order.price().inverse())
+        .max()
+}
+
+// Conditional book
+// ================================================================================================
+
+/// Conditional orders persisted in the local conditional orders file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConditionalBook {
+    pub orders: Vec<ConditionalOrder>,
+}
+
+impl ConditionalBook {
+    pub fn load() -> Result<Self, String> {
+        load_toml(CONDITIONAL_ORDERS_FILE_PATH)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_toml(CONDITIONAL_ORDERS_FILE_PATH, self)
+    }
+
+    /// Adds a pending conditional order, returning its local id.
+    pub fn add(
+        &mut self,
+        account_id: AccountId,
+        source_asset: FungibleAsset,
+        target_asset: FungibleAsset,
+        kind: ConditionalKind,
+        trigger_price: Price,
+        time_in_force: TimeInForce,
+    ) -> u32 {
+        let id = self
+            .orders
+            .iter()
+            .map(|order| order.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.orders.push(ConditionalOrder {
+            id,
+            account_id,
+            source_faucet_id: source_asset.faucet_id(),
+            target_faucet_id: target_asset.faucet_id(),
+            source_amount: source_asset.amount(),
+            target_amount: target_asset.amount(),
+            kind,
+            trigger_price,
+            time_in_force,
+            triggered_at: None,
+            status: ConditionalStatus::Pending,
+        });
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut ConditionalOrder> {
+        self.orders.iter_mut().find(|order| order.id == id)
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+    };
+
+    use super::{best_price, ConditionalBook, ConditionalKind, ConditionalStatus};
+    use crate::{
+        order::{Order, TimeInForce},
+        price::Price,
+    };
+
+    #[test]
+    fn conditional_orders_trigger_on_the_best_price() {
+        let account_id = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let source_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let target_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let asset = |faucet_id, amount| FungibleAsset::new(faucet_id, amount).unwrap();
+
+        // resting orders paying 2 and 3 target per source
+        let orders = [(200, 100), (300, 100)].map(|(offered, requested)| {
+            Order::new_partial(
+                None,
+                Asset::Fungible(asset(target_faucet_id, offered)),
+                Asset::Fungible(asset(source_faucet_id, requested)),
+            )
+        });
+        let best = best_price(source_faucet_id, target_faucet_id, &orders).unwrap();
+        assert_eq!(best, Price::new(3, 1));
+        assert_eq!(
+            best_price(target_faucet_id, source_faucet_id, &orders),
+            None
+        );
+
+        let mut book = ConditionalBook::default();
+        let mut add = |kind, trigger_price| {
+            book.add(
+                account_id,
+                asset(source_faucet_id, 100),
+                asset(target_faucet_id, 250),
+                kind,
+                trigger_price,
+                TimeInForce::ImmediateOrCancel,
+            )
+        };
+        let stop_loss = add(ConditionalKind::StopLoss, Price::new(5, 2));
+        let take_profit = add(ConditionalKind::TakeProfit, Price::new(3, 1));
+
+        assert!(!book.get_mut(stop_loss).unwrap().is_triggered(best));
+        assert!(book
+            .get_mut(stop_loss)
+            .unwrap()
+            .is_triggered(Price::new(2, 1)));
+        assert!(book.get_mut(take_profit).unwrap().is_triggered(best));
+
+        let order = book.get_mut(take_profit).unwrap();
+        order.trigger(42, true);
+        assert_eq!(order.status, ConditionalStatus::Triggered);
+        assert_eq!(order.triggered_at, Some(42));
+        assert!(!order.is_pending());
+    }
+}
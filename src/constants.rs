@@ -1,3 +1,14 @@
 pub const DB_FILE_PATH: &str = "store.sqlite3";
 pub const CLOB_DATA_FILE_PATH: &str = "clob_data.toml";
+pub const ICEBERGS_FILE_PATH: &str = "icebergs.toml";
+pub const CONDITIONAL_ORDERS_FILE_PATH: &str = "conditional_orders.toml";
+pub const TWAPS_FILE_PATH: &str = "twaps.toml";
+pub const SHARED_ORDERS_FILE_PATH: &str = "shared_orders.toml";
+pub const OTC_OFFERS_FILE_PATH: &str = "otc_offers.toml";
 pub const ACCOUNTS_DIR: &str = "accounts";
+pub const DEFAULT_SELECTION_BUDGET_MS: u64 = 200;
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u32 = 100;
+pub const DEFAULT_MAX_HOPS: usize = 3;
+pub const DEFAULT_AUCTION_WINDOW: u32 = 10;
+pub const DEFAULT_WATCH_INTERVAL_SECS: u64 = 10;
+pub const DEFAULT_DEPTH_LEVELS: usize = 20;
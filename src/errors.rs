@@ -1,11 +1,176 @@
-use crate::order::Order;
+use core::fmt;
+
+use miden_client::{accounts::AccountId, notes::NoteId, ClientError};
+use miden_objects::Digest;
+
+use crate::{order::Order, safety::VaultMismatch};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum OrderError {
     AssetsNotMatching,
-    TooFewSourceAssets,
+    PriceNotMatching,
     TooManyTargetAssets,
+    InvalidFillAmount,
     FailedFill(Order),
+    WouldCross,
+    SelfTrade,
     MissingId,
+    MissingCreator,
     InternalError(String),
 }
+
+/// Reasons a note cannot be decoded as a [SwapNote](crate::notes::SwapNote).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapNoteError {
+    /// The note script is neither the SWAP nor the SWAPp script.
+    UnknownScript(Digest),
+    /// The note does not have the number of inputs its script expects.
+    WrongInputCount { expected: usize, found: usize },
+    /// The note carries no asset to offer.
+    NoOfferedAsset,
+    /// The note offers several assets, while the swap scripts only hand over one.
+    MultipleOfferedAssets(usize),
+    /// The requested asset in the note inputs is not a valid asset.
+    InvalidRequestedAsset(String),
+    /// The payback tag in the note inputs is not a valid tag.
+    InvalidPaybackTag(u64),
+    /// The payback script root in the SWAPp note inputs is not the P2ID script root.
+    UnknownPaybackScript(Digest),
+    /// The creator in the SWAPp note inputs is not a valid account id.
+    InvalidCreator(String),
+    /// The tag in the SWAPp note inputs differs from the tag of the note.
+    TagMismatch { inputs: u64, metadata: u32 },
+    /// The note type in the SWAPp note inputs is not a valid note type.
+    InvalidNoteType(u64),
+    /// The counterparty in the SWAPp note inputs is not a valid account id.
+    InvalidCounterparty(String),
+}
+
+impl fmt::Display for SwapNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwapNoteError::UnknownScript(root) => {
+                write!(f, "script {} is not a known swap script", root)
+            }
+            SwapNoteError::WrongInputCount { expected, found } => {
+                write!(f, "{} note inputs instead of {}", found, expected)
+            }
+            SwapNoteError::NoOfferedAsset => write!(f, "no offered asset"),
+            SwapNoteError::MultipleOfferedAssets(count) => {
+                write!(f, "{} offered assets instead of one", count)
+            }
+            SwapNoteError::InvalidRequestedAsset(err) => {
+                write!(f, "invalid requested asset: {}", err)
+            }
+            SwapNoteError::InvalidPaybackTag(tag) => write!(f, "invalid payback tag {}", tag),
+            SwapNoteError::UnknownPaybackScript(root) => {
+                write!(f, "payback script {} is not the P2ID script", root)
+            }
+            SwapNoteError::InvalidCreator(err) => write!(f, "invalid creator: {}", err),
+            SwapNoteError::TagMismatch { inputs, metadata } => write!(
+                f,
+                "tag {} in the inputs differs from the note tag {}",
+                inputs, metadata
+            ),
+            SwapNoteError::InvalidNoteType(note_type) => {
+                write!(f, "invalid note type {}", note_type)
+            }
+            SwapNoteError::InvalidCounterparty(err) => write!(f, "invalid counterparty: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SwapNoteError {}
+
+/// Errors returned by the [OrderBookClient](crate::client::OrderBookClient) API.
+#[derive(Debug)]
+pub enum OrderBookError {
+    /// The Miden client failed to query its store, the node, or to execute a transaction.
+    Client(ClientError),
+    /// An amount cannot be represented as an asset of its faucet.
+    InvalidAsset(String),
+    /// The account does not hold enough of an asset to execute the order.
+    InsufficientBalance {
+        faucet_id: AccountId,
+        required: u64,
+        available: u64,
+    },
+    /// The note is not known by the client.
+    UnknownNote(NoteId),
+    /// The note is not a valid swap note.
+    InvalidNote(NoteId, SwapNoteError),
+    /// The orders were not created by the account cancelling them.
+    NotCreator(Vec<NoteId>),
+    /// The order could not be matched against the book.
+    Order(OrderError),
+    /// Notes selected for a fill run a script that is not a trusted swap script.
+    UntrustedScripts(Vec<(NoteId, Digest)>),
+    /// The fill simulated locally changes the vault differently than the fills promise.
+    UnexpectedVaultDelta(Vec<VaultMismatch>),
+    /// The note cannot be exported to, or imported from, a note file.
+    InvalidNoteFile(String),
+}
+
+impl fmt::Display for OrderBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderBookError::Client(err) => write!(f, "client error: {}", err),
+            OrderBookError::InvalidAsset(err) => write!(f, "invalid asset: {}", err),
+            OrderBookError::InsufficientBalance {
+                faucet_id,
+                required,
+                available,
+            } => write!(
+                f,
+                "not enough assets of faucet {}: {} required, {} available",
+                faucet_id, required, available
+            ),
+            OrderBookError::UnknownNote(note_id) => write!(f, "unknown note {}", note_id),
+            OrderBookError::InvalidNote(note_id, err) => {
+                write!(f, "invalid swap note {}: {}", note_id, err)
+            }
+            OrderBookError::NotCreator(note_ids) => write!(
+                f,
+                "orders not created by the account: {}",
+                note_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            OrderBookError::Order(err) => write!(f, "order error: {:?}", err),
+            OrderBookError::UntrustedScripts(notes) => {
+                write!(f, "notes with an untrusted script, fill aborted:")?;
+                for (note_id, script_root) in notes {
+                    write!(f, "\n  note {}: script {}", note_id, script_root)?;
+                }
+                Ok(())
+            }
+            OrderBookError::UnexpectedVaultDelta(mismatches) => {
+                write!(
+                    f,
+                    "simulated vault changes differ from the balance update, fill aborted:"
+                )?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+            OrderBookError::InvalidNoteFile(err) => write!(f, "invalid note file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OrderBookError {}
+
+impl From<ClientError> for OrderBookError {
+    fn from(err: ClientError) -> Self {
+        OrderBookError::Client(err)
+    }
+}
+
+impl From<OrderError> for OrderBookError {
+    fn from(err: OrderError) -> Self {
+        OrderBookError::Order(err)
+    }
+}
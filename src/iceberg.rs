@@ -0,0 +1,201 @@
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+    Word,
+};
+use miden_objects::Digest;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::ICEBERGS_FILE_PATH,
+    notes::is_leftover_serial,
+    order::Order,
+    price::Price,
+    utils::{load_toml, save_toml},
+};
+
+// Iceberg
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IcebergStatus {
+    /// A slice is resting in the order book.
+    Open,
+    /// Every slice has been filled.
+    Completed,
+    /// The resting slice was cancelled by its creator.
+    Cancelled,
+}
+
+/// A large order of which only a slice is visible in the order book at any time. The hidden part
+/// is tracked locally and a new slice is posted whenever the previous one has been filled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Iceberg {
+    pub id: u32,
+    pub account_id: AccountId,
+    pub source_faucet_id: AccountId,
+    pub target_faucet_id: AccountId,
+    /// Total amount offered, with `target_amount` setting the limit price of every slice.
+    pub source_amount: u64,
+    pub target_amount: u64,
+    /// Amount offered by each visible slice.
+    pub visible_amount: u64,
+    /// Amount offered that has not been posted yet.
+    pub hidden_amount: u64,
+    /// Note id of the resting slice, as hex.
+    pub note_id: Option<String>,
+    /// Serial number of the resting slice, as hex, used to follow the notes re-emitted by partial
+    /// fills.
+    pub serial_num: Option<String>,
+    pub status: IcebergStatus,
+}
+
+impl Iceberg {
+    pub fn is_open(&self) -> bool {
+        self.status == IcebergStatus::Open
+    }
+
+    /// Limit price of every slice, as amount requested per unit offered.
+    pub fn price(&self) -> Price {
+        Price::new(self.target_amount, self.source_amount)
+    }
+
+    /// Order of the next slice to post, or `None` if everything has been posted.
+    pub fn next_slice(&self) -> Option<Order> {
+        let source_amount = self.visible_amount.min(self.hidden_amount);
+        if source_amount == 0 {
+            return None;
+        }
+
+        // the requested amount is rounded up so a slice never sells below the limit price
+        let target_amount = self.price().mul_ceil(source_amount)?;
+        let source_asset = FungibleAsset::new(self.source_faucet_id, source_amount).ok()?;
+        let target_asset = FungibleAsset::new(self.target_faucet_id, target_amount).ok()?;
+
+        Some(Order::new(
+            None,
+            Asset::Fungible(source_asset),
+            Asset::Fungible(target_asset),
+        ))
+    }
+
+    /// Records `slice` as the resting slice, identified by its note id and serial number.
+    pub fn post_slice(&mut self, slice: &Order, note_id: String, serial_num: Word) {
+        self.hidden_amount -= slice.source_asset().unwrap_fungible().amount();
+        self.note_id = Some(note_id);
+        self.serial_num = Some(Digest::from(serial_num).to_hex());
+    }
+
+    /// Whether `serial_num` is the serial number of a note re-emitted by a partial fill of the
+    /// resting slice.
+    pub fn is_leftover(&self, serial_num: Word) -> bool {
+        self.serial_num
+            .as_deref()
+            .and_then(|hex| Digest::try_from(hex).ok())
+            .is_some_and(|current| is_leftover_serial(current.into(), serial_num))
+    }
+}
+
+// Iceberg book
+// ================================================================================================
+
+/// Icebergs persisted in the local icebergs file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IcebergBook {
+    pub icebergs: Vec<Iceberg>,
+}
+
+impl IcebergBook {
+    pub fn load() -> Result<Self, String> {
+        load_toml(ICEBERGS_FILE_PATH)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_toml(ICEBERGS_FILE_PATH, self)
+    }
+
+    /// Adds an iceberg with nothing posted yet, returning its local id.
+    pub fn add(
+        &mut self,
+        account_id: AccountId,
+        source_asset: FungibleAsset,
+        target_asset: FungibleAsset,
+        visible_amount: u64,
+    ) -> u32 {
+        let id = self
+            .icebergs
+            .iter()
+            .map(|iceberg| iceberg.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.icebergs.push(Iceberg {
+            id,
+            account_id,
+            source_faucet_id: source_asset.faucet_id(),
+            target_faucet_id: target_asset.faucet_id(),
+            source_amount: source_asset.amount(),
+            target_amount: target_asset.amount(),
+            visible_amount,
+            hidden_amount: source_asset.amount(),
+            note_id: None,
+            serial_num: None,
+            status: IcebergStatus::Open,
+        });
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Iceberg> {
+        self.icebergs.iter_mut().find(|iceberg| iceberg.id == id)
+    }
+
+    /// Open iceberg whose resting slice is the note `note_id`.
+    pub fn find_by_note_mut(&mut self, note_id: &str) -> Option<&mut Iceberg> {
+        self.icebergs
+            .iter_mut()
+            .find(|iceberg| iceberg.is_open() && iceberg.note_id.as_deref() == Some(note_id))
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{accounts::AccountId, assets::FungibleAsset, Felt};
+
+    use super::IcebergBook;
+
+    #[test]
+    fn slices_are_posted_until_the_iceberg_is_exhausted() {
+        let account_id = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let source_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let target_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+
+        let mut book = IcebergBook::default();
+        let id = book.add(
+            account_id,
+            FungibleAsset::new(source_faucet_id, 250).unwrap(),
+            FungibleAsset::new(target_faucet_id, 100).unwrap(),
+            100,
+        );
+        let iceberg = book.get_mut(id).unwrap();
+
+        let serial_num = [Felt::new(1), Felt::new(2), Felt::new(3), Felt::new(4)];
+        let mut posted = Vec::new();
+        while let Some(slice) = iceberg.next_slice() {
+            posted.push((
+                slice.source_asset().unwrap_fungible().amount(),
+                slice.target_asset().unwrap_fungible().amount(),
+            ));
+            iceberg.post_slice(&slice, "0x01".to_string(), serial_num);
+        }
+
+        // the last slice is rounded up to stay at the limit price
+        assert_eq!(posted, vec![(100, 40), (100, 40), (50, 20)]);
+        assert_eq!(iceberg.hidden_amount, 0);
+
+        assert!(iceberg.is_leftover([Felt::new(1), Felt::new(2), Felt::new(3), Felt::new(5)]));
+        assert!(!iceberg.is_leftover(serial_num));
+        assert!(!iceberg.is_leftover([Felt::new(2), Felt::new(2), Felt::new(3), Felt::new(5)]));
+    }
+}
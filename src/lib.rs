@@ -0,0 +1,38 @@
+//! Order book on the Miden rollup, where orders are partially fillable swap (SWAPp) notes.
+//!
+//! [client::OrderBookClient] wraps a Miden client to load the [book::OrderBook] of a market,
+//! quote, place and cancel orders, and sync with the node. The matching engine itself works on
+//! plain [order::Order] values and can be used without a client:
+//!
+//! ```ignore
+//! use std::time::Duration;
+//!
+//! use miden_order_book::{client::OrderBookClient, order::Order};
+//!
+//! let mut book_client = OrderBookClient::new(&mut client);
+//! book_client.sync().await?;
+//!
+//! let order = Order::new(None, offered_asset, requested_asset);
+//! let quote = book_client.quote(account_id, order, Duration::from_millis(500))?;
+//! println!("average price: {:?}", quote.average_price());
+//!
+//! let placement = book_client.place(account_id, order, Duration::from_millis(500)).await?;
+//! ```
+
+pub mod book;
+pub mod client;
+pub mod conditional;
+pub mod constants;
+pub mod errors;
+pub mod iceberg;
+pub mod market;
+pub mod notes;
+pub mod order;
+pub mod otc;
+pub mod price;
+pub mod router;
+pub mod safety;
+pub mod selection;
+pub mod sharing;
+pub mod twap;
+pub mod utils;
@@ -3,14 +3,14 @@ use cli::Cli;
 
 mod cli;
 mod commands;
-mod constants;
-mod errors;
-mod order;
-mod utils;
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
-    env_logger::init();
+    // Notes skipped while loading the order book are reported as warnings
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("miden_order_book=warn"),
+    )
+    .init();
 
     let cli = Cli::parse();
 
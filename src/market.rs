@@ -0,0 +1,145 @@
+use miden_client::accounts::AccountId;
+
+use crate::{
+    book::{OrderBook, Side},
+    order::Order,
+    price::Price,
+};
+
+// Volume
+// ================================================================================================
+
+/// Amounts exchanged by the orders of a market filled since a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Volume {
+    /// Number of notes consumed by a counterparty.
+    pub trades: usize,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+impl Volume {
+    /// Adds the fill of `order`, resting on `side`, which re-emitted `leftover` if it was only
+    /// partially filled.
+    pub fn add_fill(&mut self, side: Side, order: &Order, leftover: Option<&Order>) {
+        let base_left = leftover.map_or(0, |leftover| OrderBook::base_amount(side, leftover));
+        let quote_left = leftover.map_or(0, |leftover| OrderBook::quote_amount(side, leftover));
+
+        self.trades += 1;
+        self.base_amount += OrderBook::base_amount(side, order).saturating_sub(base_left);
+        self.quote_amount += OrderBook::quote_amount(side, order).saturating_sub(quote_left);
+    }
+}
+
+// Market summary
+// ================================================================================================
+
+/// Top of the book, resting liquidity and traded volume of a market at a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketSummary {
+    pub base_faucet_id: AccountId,
+    pub quote_faucet_id: AccountId,
+    pub block_num: u32,
+    pub best_bid: Option<Price>,
+    pub best_ask: Option<Price>,
+    pub spread_bps: Option<i64>,
+    pub mid_price: Option<Price>,
+    pub bid_notes: usize,
+    pub ask_notes: usize,
+    /// Size of the bids, in the base asset.
+    pub bid_liquidity: u64,
+    /// Size of the asks, in the base asset.
+    pub ask_liquidity: u64,
+    /// First block the volume is counted from.
+    pub since_block: u32,
+    pub volume: Volume,
+}
+
+impl MarketSummary {
+    pub fn new(book: &OrderBook, since_block: u32, volume: Volume) -> Self {
+        Self {
+            base_faucet_id: book.base_faucet_id(),
+            quote_faucet_id: book.quote_faucet_id(),
+            block_num: book.block_num(),
+            best_bid: book.best_bid_price(),
+            best_ask: book.best_ask_price(),
+            spread_bps: book.spread_bps(),
+            mid_price: book.mid_price(),
+            bid_notes: book.orders(Side::Bid).len(),
+            ask_notes: book.orders(Side::Ask).len(),
+            bid_liquidity: book.liquidity(Side::Bid),
+            ask_liquidity: book.liquidity(Side::Ask),
+            since_block,
+            volume,
+        }
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+        notes::NoteId,
+    };
+
+    use super::{MarketSummary, Volume};
+    use crate::{
+        book::{OrderBook, Side},
+        order::Order,
+        price::Price,
+    };
+
+    #[test]
+    fn summaries_count_liquidity_and_filled_volume() {
+        let base_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let quote_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let order = |id: u8, offered: AccountId, offered_amount, requested: AccountId, amount| {
+            Order::new(
+                Some(NoteId::try_from_hex(&format!("0x{:064x}", id)).unwrap()),
+                Asset::Fungible(FungibleAsset::new(offered, offered_amount).unwrap()),
+                Asset::Fungible(FungibleAsset::new(requested, amount).unwrap()),
+            )
+        };
+
+        // an ask fully filled, and a bid of 20 base for 10 quote re-emitted with 8 base left
+        let mut volume = Volume::default();
+        volume.add_fill(
+            Side::Ask,
+            &order(1, base_faucet_id, 10, quote_faucet_id, 20),
+            None,
+        );
+        let bid = order(2, quote_faucet_id, 10, base_faucet_id, 20);
+        let leftover = order(3, quote_faucet_id, 4, base_faucet_id, 8);
+        volume.add_fill(Side::Bid, &bid, Some(&leftover));
+        assert_eq!(
+            volume,
+            Volume {
+                trades: 2,
+                base_amount: 22,
+                quote_amount: 26
+            }
+        );
+
+        let book = OrderBook::new(
+            base_faucet_id,
+            quote_faucet_id,
+            10,
+            vec![
+                leftover,
+                order(4, base_faucet_id, 10, quote_faucet_id, 10),
+                order(5, base_faucet_id, 5, quote_faucet_id, 10),
+            ],
+        );
+        let summary = MarketSummary::new(&book, 5, volume);
+        assert_eq!(summary.best_bid, Some(Price::new(1, 2)));
+        assert_eq!(summary.best_ask, Some(Price::new(1, 1)));
+        assert_eq!(summary.mid_price, Some(Price::new(3, 4)));
+        assert_eq!((summary.bid_notes, summary.bid_liquidity), (1, 8));
+        assert_eq!((summary.ask_notes, summary.ask_liquidity), (2, 15));
+        assert_eq!(summary.volume.trades, 2);
+    }
+}
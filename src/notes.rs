@@ -0,0 +1,762 @@
+use std::sync::OnceLock;
+
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+    crypto::FeltRng,
+    notes::{
+        Note, NoteAssets, NoteExecutionMode, NoteId, NoteInputs, NoteMetadata, NoteRecipient,
+        NoteScript, NoteTag, NoteType,
+    },
+    store::InputNoteRecord,
+    transactions::build_swap_tag,
+    Felt, Word,
+};
+use miden_lib::{
+    notes::{create_swap_note, scripts, utils::build_p2id_recipient},
+    transaction::TransactionKernel,
+};
+use miden_objects::{notes::NoteDetails, Digest, NoteError};
+
+use crate::{
+    errors::{OrderError, SwapNoteError},
+    order::Order,
+};
+
+// SWAPp note script
+// ================================================================================================
+
+/// Source of the partially fillable swap note script.
+const SWAPP_SCRIPT: &str = include_str!("../masm/notes/SWAPP.masm");
+
+/// Number of inputs the SWAPp note script expects.
+pub const SWAPP_NUM_INPUTS: usize = 16;
+
+/// Number of inputs the standard SWAP note script expects.
+pub const SWAP_NUM_INPUTS: usize = 9;
+
+/// Index of the requested asset word in the inputs of both swap scripts, after the payback
+/// recipient of SWAP notes or the payback script root of SWAPp notes.
+pub const REQUESTED_ASSET_INPUT: usize = 4;
+
+/// Index of the payback note tag in the inputs of both swap scripts.
+pub const PAYBACK_TAG_INPUT: usize = 8;
+
+/// Index of the note tag in the SWAPp note inputs.
+pub const SWAPP_TAG_INPUT: usize = 9;
+
+/// Index of the creator account id in the SWAPp note inputs.
+pub const SWAPP_CREATOR_INPUT: usize = 10;
+
+/// Index of the note type in the SWAPp note inputs, shared by the notes re-emitted on partial
+/// fills.
+pub const SWAPP_NOTE_TYPE_INPUT: usize = 11;
+
+/// Index of the counterparty account id in the SWAPp note inputs, zero if anyone can fill the
+/// note.
+pub const SWAPP_COUNTERPARTY_INPUT: usize = 13;
+
+pub fn swapp_script() -> NoteScript {
+    let assembler = TransactionKernel::assembler();
+    NoteScript::compile(SWAPP_SCRIPT, assembler).expect("SWAPp script should compile")
+}
+
+pub fn swapp_script_root() -> Digest {
+    static ROOT: OnceLock<Digest> = OnceLock::new();
+    *ROOT.get_or_init(|| swapp_script().hash())
+}
+
+/// Serial number of the note re-emitted by a partial fill of a SWAPp note with serial number
+/// `serial_num`. The SWAPp script increments its last element.
+pub fn leftover_serial(serial_num: Word) -> Word {
+    let mut leftover_serial_num = serial_num;
+    leftover_serial_num[3] += Felt::new(1);
+    leftover_serial_num
+}
+
+/// Whether `leftover` is the serial number of the note re-emitted by a partial fill of a SWAPp
+/// note with serial number `serial_num`, see [leftover_serial].
+pub fn is_leftover_serial(serial_num: Word, leftover: Word) -> bool {
+    leftover_serial(serial_num) == leftover
+}
+
+/// Serial number of the P2ID note paying back the fill of a SWAPp note with serial number
+/// `serial_num`. The SWAPp script increments its first element, the leftover note its last one, so
+/// that successive fills of an order pay distinct notes.
+pub fn payback_serial(serial_num: Word) -> Word {
+    let mut payback_serial_num = serial_num;
+    payback_serial_num[0] += Felt::new(1);
+    payback_serial_num
+}
+
+/// Recipient of the P2ID note paying `creator` back for the fill of a SWAPp note with serial number
+/// `serial_num`.
+pub fn payback_recipient(creator: AccountId, serial_num: Word) -> Result<NoteRecipient, NoteError> {
+    build_p2id_recipient(creator, payback_serial(serial_num))
+}
+
+/// Rebuilds the note re-emitted by the SWAPp script when `filler` fills `fill_amount` of the
+/// requested asset of `note`, none if nothing is left. The requested amount is reduced by the fill
+/// amount and the offered amount by its pro rata share, rounded down.
+pub fn leftover_note(
+    note: &Note,
+    fill_amount: u64,
+    filler: AccountId,
+) -> Result<Option<Note>, OrderError> {
+    let internal_error =
+        |e: NoteError| OrderError::InternalError(format!("Failed to rebuild leftover note: {}", e));
+
+    let mut inputs = note.inputs().values().to_vec();
+    let Some(Asset::Fungible(offered)) = note.assets().iter().next().copied() else {
+        return Err(OrderError::InternalError(
+            "SWAPp notes offer a single fungible asset".to_string(),
+        ));
+    };
+    if inputs.len() != SWAPP_NUM_INPUTS {
+        return Err(OrderError::InternalError(format!(
+            "{} note inputs instead of {}",
+            inputs.len(),
+            SWAPP_NUM_INPUTS
+        )));
+    }
+
+    let requested_amount = inputs[REQUESTED_ASSET_INPUT].as_int();
+    if fill_amount == 0 || fill_amount > requested_amount {
+        return Err(OrderError::InvalidFillAmount);
+    }
+    let offered_out = fill_amount
+        .checked_mul(offered.amount())
+        .ok_or(OrderError::InvalidFillAmount)?
+        / requested_amount;
+    let leftover_amount = offered.amount() - offered_out;
+    if leftover_amount == 0 {
+        return Ok(None);
+    }
+    inputs[REQUESTED_ASSET_INPUT] = Felt::new(requested_amount - fill_amount);
+
+    let note_type = NoteType::try_from(inputs[SWAPP_NOTE_TYPE_INPUT]).map_err(internal_error)?;
+    let tag = u32::try_from(inputs[SWAPP_TAG_INPUT].as_int())
+        .map(NoteTag::from)
+        .map_err(|e| OrderError::InternalError(e.to_string()))?;
+    let metadata =
+        NoteMetadata::new(filler, note_type, tag, note.metadata().aux()).map_err(internal_error)?;
+    let leftover = FungibleAsset::new(offered.faucet_id(), leftover_amount)
+        .map_err(|e| OrderError::InternalError(e.to_string()))?;
+    let assets = NoteAssets::new(vec![Asset::Fungible(leftover)]).map_err(internal_error)?;
+    let serial_num = leftover_serial(note.serial_num());
+    let inputs = NoteInputs::new(inputs).map_err(internal_error)?;
+    let recipient = NoteRecipient::new(serial_num, note.script().clone(), inputs);
+
+    Ok(Some(Note::new(assets, metadata, recipient)))
+}
+
+// SWAPp note creation
+// ================================================================================================
+
+/// Creates a partially fillable swap note offering `offered_asset` for `requested_asset`.
+///
+/// The note shares its tag with regular swap notes of the same pair. When it is consumed the
+/// payment is sent back to `creator` in a public P2ID note, whose serial number is derived from the
+/// one of the filled note, and the unfilled part is re-emitted as a new SWAPp note with the same
+/// inputs and a reduced requested amount.
+///
+/// The expiration block is stored in the note `aux` felt, zero meaning the note never expires.
+/// Once expired the note can only be consumed by `creator` to reclaim the offered asset.
+///
+/// A note directed to a `counterparty` can only be filled by that account. It is tagged for the
+/// counterparty instead of the pair, so that it is received by the counterparty on sync and stays
+/// out of the order books.
+pub fn create_partial_swap_note<R: FeltRng>(
+    creator: AccountId,
+    offered_asset: Asset,
+    requested_asset: Asset,
+    note_type: NoteType,
+    expiration: Option<u32>,
+    counterparty: Option<AccountId>,
+    rng: &mut R,
+) -> Result<Note, NoteError> {
+    let aux = Felt::from(expiration.unwrap_or(0));
+
+    let payback_script_root: Word = scripts::p2id().hash().into();
+    let requested_asset_word: Word = requested_asset.into();
+    let payback_tag = NoteTag::from_account_id(creator, NoteExecutionMode::Local)?;
+
+    let tag = match counterparty {
+        Some(counterparty) => NoteTag::from_account_id(counterparty, NoteExecutionMode::Local)?,
+        None => build_swap_tag(
+            note_type,
+            offered_asset.faucet_id(),
+            requested_asset.faucet_id(),
+        )?,
+    };
+
+    let inputs = NoteInputs::new(vec![
+        payback_script_root[0],
+        payback_script_root[1],
+        payback_script_root[2],
+        payback_script_root[3],
+        requested_asset_word[0],
+        requested_asset_word[1],
+        requested_asset_word[2],
+        requested_asset_word[3],
+        payback_tag.inner().into(),
+        tag.inner().into(),
+        creator.into(),
+        note_type.into(),
+        aux,
+        counterparty.map_or(Felt::new(0), Felt::from),
+        Felt::new(0),
+        Felt::new(0),
+    ])?;
+
+    let metadata = NoteMetadata::new(creator, note_type, tag, aux)?;
+    let assets = NoteAssets::new(vec![offered_asset])?;
+    let serial_num = rng.draw_word();
+    let recipient = NoteRecipient::new(serial_num, swapp_script(), inputs);
+
+    Ok(Note::new(assets, metadata, recipient))
+}
+
+/// Creates the note resting `order` in the order book until its expiration block, public or
+/// private as set by the order.
+///
+/// Orders trading a non-fungible asset cannot be split, they rest in a regular SWAP note filled as
+/// a whole. The SWAP script neither enforces an expiration block nor restricts who fills the note,
+/// so such orders can neither expire nor be directed to a counterparty.
+pub fn create_order_note<R: FeltRng>(
+    order: Order,
+    account_id: AccountId,
+    rng: &mut R,
+) -> Result<Note, OrderError> {
+    let note = if order.is_fungible() {
+        create_partial_swap_note(
+            account_id,
+            order.source_asset(),
+            order.target_asset(),
+            order.note_type(),
+            order.expiration(),
+            order.counterparty(),
+            rng,
+        )
+    } else if order.counterparty().is_some() {
+        return Err(OrderError::InternalError(
+            "Orders directed to a counterparty cannot trade non-fungible assets".to_string(),
+        ));
+    } else if order.expiration().is_some() {
+        return Err(OrderError::InternalError(
+            "Orders trading non-fungible assets cannot expire".to_string(),
+        ));
+    } else {
+        create_swap_note(
+            account_id,
+            order.source_asset(),
+            order.target_asset(),
+            order.note_type(),
+            Felt::new(0),
+            rng,
+        )
+        .map(|(note, _)| note)
+    };
+
+    note.map_err(|e| OrderError::InternalError(format!("Failed to create swap note: {}", e)))
+}
+
+/// Tags of the public and private swap notes offering `offered` for `requested`. Private notes
+/// are only in the store once imported, but they are listed and filled like public ones.
+pub fn swap_tags(offered: AccountId, requested: AccountId) -> Result<[NoteTag; 2], NoteError> {
+    Ok([
+        build_swap_tag(NoteType::Public, offered, requested)?,
+        build_swap_tag(NoteType::Private, offered, requested)?,
+    ])
+}
+
+// Swap note decoding
+// ================================================================================================
+
+/// A SWAP or SWAPp note whose inputs have been checked against the layout of its script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapNote {
+    id: NoteId,
+    partial: bool,
+    offered_assets: Vec<Asset>,
+    requested_asset: Asset,
+    payback_recipient: Option<Digest>,
+    payback_tag: NoteTag,
+    tag: Option<NoteTag>,
+    creator: Option<AccountId>,
+    expiration: Option<u32>,
+    note_type: NoteType,
+    counterparty: Option<AccountId>,
+}
+
+impl SwapNote {
+    pub fn id(&self) -> NoteId {
+        self.id
+    }
+
+    /// Whether the note is a partially fillable SWAPp note rather than a standard SWAP note.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Assets carried by the note, never empty. Only notes carrying a single asset are orders, the
+    /// swap scripts refusing the others.
+    pub fn offered_assets(&self) -> &[Asset] {
+        &self.offered_assets
+    }
+
+    pub fn requested_asset(&self) -> Asset {
+        self.requested_asset
+    }
+
+    /// Recipient digest of the note paying the requested asset back to the creator of a SWAP note.
+    /// SWAPp notes pay every fill to another recipient, see [payback_recipient].
+    pub fn payback_recipient(&self) -> Option<Digest> {
+        self.payback_recipient
+    }
+
+    /// Tag of the note paying the requested asset back to the creator.
+    pub fn payback_tag(&self) -> NoteTag {
+        self.payback_tag
+    }
+
+    /// Tag of the note, known once its metadata has been synced.
+    pub fn tag(&self) -> Option<NoteTag> {
+        self.tag
+    }
+
+    /// Account that created the order: the creator in the SWAPp note inputs, since leftover
+    /// notes are sent by the filler, or the sender of a SWAP note.
+    pub fn creator(&self) -> Option<AccountId> {
+        self.creator
+    }
+
+    /// Last block at which a SWAPp note can be filled, stored in the note `aux` felt. SWAP notes
+    /// never expire, their script does not check it.
+    pub fn expiration(&self) -> Option<u32> {
+        self.expiration
+    }
+
+    /// Whether the note is public or private, from the SWAPp inputs or the note metadata.
+    pub fn note_type(&self) -> NoteType {
+        self.note_type
+    }
+
+    /// Only account besides the creator allowed to consume the note, if it is directed.
+    pub fn counterparty(&self) -> Option<AccountId> {
+        self.counterparty
+    }
+}
+
+impl SwapNote {
+    /// Decodes the note of `id` from its script root, inputs and assets. The metadata is known
+    /// once the note is synced, the tag can also come with the details of a note shared
+    /// off-chain.
+    fn decode(
+        id: NoteId,
+        script_root: Digest,
+        inputs: &[Felt],
+        assets: &NoteAssets,
+        metadata: Option<&NoteMetadata>,
+        note_tag: Option<NoteTag>,
+    ) -> Result<Self, SwapNoteError> {
+        let (partial, expected_inputs) = if script_root == swapp_script_root() {
+            (true, SWAPP_NUM_INPUTS)
+        } else if script_root == scripts::swap().hash() {
+            (false, SWAP_NUM_INPUTS)
+        } else {
+            return Err(SwapNoteError::UnknownScript(script_root));
+        };
+
+        if inputs.len() != expected_inputs {
+            return Err(SwapNoteError::WrongInputCount {
+                expected: expected_inputs,
+                found: inputs.len(),
+            });
+        }
+        let word = |index: usize| -> Word {
+            [
+                inputs[index],
+                inputs[index + 1],
+                inputs[index + 2],
+                inputs[index + 3],
+            ]
+        };
+
+        let offered_assets: Vec<Asset> = assets.iter().copied().collect();
+        if offered_assets.is_empty() {
+            return Err(SwapNoteError::NoOfferedAsset);
+        }
+        let requested_asset = Asset::try_from(word(REQUESTED_ASSET_INPUT))
+            .map_err(|e| SwapNoteError::InvalidRequestedAsset(e.to_string()))?;
+
+        let payback_tag = inputs[PAYBACK_TAG_INPUT].as_int();
+        let payback_tag = u32::try_from(payback_tag)
+            .map(NoteTag::from)
+            .map_err(|_| SwapNoteError::InvalidPaybackTag(payback_tag))?;
+
+        // SWAPp notes build the payback recipient of each fill from the P2ID script root
+        let payback_recipient = Digest::from(word(0));
+        let payback_recipient = if !partial {
+            Some(payback_recipient)
+        } else if payback_recipient == scripts::p2id().hash() {
+            None
+        } else {
+            return Err(SwapNoteError::UnknownPaybackScript(payback_recipient));
+        };
+
+        let note_type = if partial {
+            let note_type = inputs[SWAPP_NOTE_TYPE_INPUT];
+            NoteType::try_from(note_type)
+                .map_err(|_| SwapNoteError::InvalidNoteType(note_type.as_int()))?
+        } else {
+            metadata.map_or(NoteType::Public, |metadata| metadata.note_type())
+        };
+        let creator = if partial {
+            let tag = inputs[SWAPP_TAG_INPUT].as_int();
+            if let Some(note_tag) = note_tag.filter(|note_tag| u64::from(note_tag.inner()) != tag) {
+                return Err(SwapNoteError::TagMismatch {
+                    inputs: tag,
+                    metadata: note_tag.inner(),
+                });
+            }
+            let creator = AccountId::try_from(inputs[SWAPP_CREATOR_INPUT])
+                .map_err(|e| SwapNoteError::InvalidCreator(e.to_string()))?;
+            Some(creator)
+        } else {
+            metadata.map(|metadata| metadata.sender())
+        };
+        let counterparty = if partial {
+            Some(inputs[SWAPP_COUNTERPARTY_INPUT])
+                .filter(|counterparty| *counterparty != Felt::new(0))
+                .map(AccountId::try_from)
+                .transpose()
+                .map_err(|e| SwapNoteError::InvalidCounterparty(e.to_string()))?
+        } else {
+            None
+        };
+
+        // The expiration block is stored in the SWAPp note aux felt, zero meaning no expiration
+        let expiration = metadata
+            .filter(|_| partial)
+            .and_then(|metadata| u32::try_from(metadata.aux().as_int()).ok())
+            .filter(|expiration| *expiration != 0);
+
+        Ok(SwapNote {
+            id,
+            partial,
+            offered_assets,
+            requested_asset,
+            payback_recipient,
+            payback_tag,
+            tag: note_tag,
+            creator,
+            expiration,
+            note_type,
+            counterparty,
+        })
+    }
+}
+
+impl TryFrom<&InputNoteRecord> for SwapNote {
+    type Error = SwapNoteError;
+
+    fn try_from(note: &InputNoteRecord) -> Result<Self, Self::Error> {
+        let metadata = note.metadata();
+        SwapNote::decode(
+            note.id(),
+            note.details().script().hash(),
+            note.details().inputs(),
+            note.assets(),
+            metadata,
+            metadata.map(|metadata| metadata.tag()),
+        )
+    }
+}
+
+impl TryFrom<&Note> for SwapNote {
+    type Error = SwapNoteError;
+
+    fn try_from(note: &Note) -> Result<Self, Self::Error> {
+        SwapNote::decode(
+            note.id(),
+            note.script().hash(),
+            note.inputs().values(),
+            note.assets(),
+            Some(note.metadata()),
+            Some(note.metadata().tag()),
+        )
+    }
+}
+
+/// Details of a note shared off-chain, with the tag it is shared under.
+impl TryFrom<(&NoteDetails, NoteTag)> for SwapNote {
+    type Error = SwapNoteError;
+
+    fn try_from((details, tag): (&NoteDetails, NoteTag)) -> Result<Self, Self::Error> {
+        SwapNote::decode(
+            details.id(),
+            details.script().hash(),
+            details.inputs().values(),
+            details.assets(),
+            None,
+            Some(tag),
+        )
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+        crypto::RpoRandomCoin,
+        notes::{Note, NoteAssets, NoteExecutionMode, NoteMetadata, NoteTag, NoteType},
+        store::InputNoteRecord,
+        Felt,
+    };
+    use miden_lib::notes::{create_p2id_note, create_swap_note};
+    use miden_objects::{
+        assets::{NonFungibleAsset, NonFungibleAssetDetails},
+        notes::NoteDetails,
+    };
+
+    use super::{
+        create_order_note, create_partial_swap_note, is_leftover_serial, leftover_note,
+        payback_recipient, payback_serial, SwapNote,
+    };
+    use crate::{errors::SwapNoteError, order::Order};
+
+    #[test]
+    fn swap_notes_are_decoded_and_validated() {
+        let creator = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let offered = asset("0x227bd163275aa1bf", 10);
+        let requested = asset("0x2540b08edc3b087d", 20);
+        let mut rng = RpoRandomCoin::new([Felt::new(1); 4]);
+
+        let note = create_partial_swap_note(
+            creator,
+            offered,
+            requested,
+            NoteType::Public,
+            Some(100),
+            None,
+            &mut rng,
+        )
+        .unwrap();
+        let swap_note = SwapNote::try_from(&InputNoteRecord::from(note.clone())).unwrap();
+        assert!(swap_note.is_partial());
+        assert_eq!(swap_note.offered_assets(), &[offered]);
+        assert_eq!(swap_note.requested_asset(), requested);
+        assert_eq!(
+            swap_note.payback_tag(),
+            NoteTag::from_account_id(creator, NoteExecutionMode::Local).unwrap()
+        );
+        assert_eq!(swap_note.tag(), Some(note.metadata().tag()));
+        assert_eq!(swap_note.creator(), Some(creator));
+        assert_eq!(swap_note.expiration(), Some(100));
+        assert_eq!(swap_note.note_type(), NoteType::Public);
+        assert_eq!(swap_note.counterparty(), None);
+        assert_eq!(swap_note.payback_recipient(), None);
+
+        // private and directed notes keep their type and counterparty in the inputs, for the notes
+        // re-emitted on partial fills, and directed notes are tagged for their counterparty
+        let counterparty = AccountId::from_hex("0x8a4e193b2c5d3e61").unwrap();
+        let note = create_partial_swap_note(
+            creator,
+            offered,
+            requested,
+            NoteType::Private,
+            None,
+            Some(counterparty),
+            &mut rng,
+        )
+        .unwrap();
+        let swap_note = SwapNote::try_from(&InputNoteRecord::from(note)).unwrap();
+        assert_eq!(swap_note.note_type(), NoteType::Private);
+        assert_eq!(swap_note.counterparty(), Some(counterparty));
+        assert_eq!(
+            swap_note.tag(),
+            Some(NoteTag::from_account_id(counterparty, NoteExecutionMode::Local).unwrap())
+        );
+
+        // notes of other scripts are rejected, even under a swap tag
+        let p2id = create_p2id_note(
+            creator,
+            creator,
+            vec![offered],
+            NoteType::Public,
+            Felt::new(0),
+            &mut rng,
+        )
+        .unwrap();
+        let script_root = p2id.script().hash();
+        assert_eq!(
+            SwapNote::try_from(&InputNoteRecord::from(p2id)),
+            Err(SwapNoteError::UnknownScript(script_root))
+        );
+    }
+
+    #[test]
+    fn shared_note_details_are_checked_against_their_tag() {
+        let creator = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let mut rng = RpoRandomCoin::new([Felt::new(5); 4]);
+        let note = create_partial_swap_note(
+            creator,
+            asset("0x227bd163275aa1bf", 10),
+            asset("0x2540b08edc3b087d", 20),
+            NoteType::Private,
+            None,
+            None,
+            &mut rng,
+        )
+        .unwrap();
+        let tag = note.metadata().tag();
+        let details = NoteDetails::new(note.assets().clone(), note.recipient().clone());
+
+        let swap_note = SwapNote::try_from((&details, tag)).unwrap();
+        assert_eq!(swap_note.id(), note.id());
+        assert_eq!(swap_note.tag(), Some(tag));
+        assert_eq!(SwapNote::try_from(&note), Ok(swap_note.clone()));
+
+        // a file shared under another tag would subscribe to it
+        let other_tag = NoteTag::from_account_id(creator, NoteExecutionMode::Local).unwrap();
+        assert_eq!(
+            SwapNote::try_from((&details, other_tag)),
+            Err(SwapNoteError::TagMismatch {
+                inputs: u64::from(tag.inner()),
+                metadata: other_tag.inner(),
+            })
+        );
+    }
+
+    #[test]
+    fn swap_notes_never_expire() {
+        let creator = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let tokens = Asset::Fungible(
+            FungibleAsset::new(AccountId::from_hex("0x227bd163275aa1bf").unwrap(), 100).unwrap(),
+        );
+        let nft_faucet_id = AccountId::from_hex("0x3d5a2f7c1b9e4c80").unwrap();
+        let details = NonFungibleAssetDetails::new(nft_faucet_id, vec![1]).unwrap();
+        let nft = Asset::NonFungible(NonFungibleAsset::new(&details).unwrap());
+        let mut rng = RpoRandomCoin::new([Felt::new(4); 4]);
+
+        // the SWAP script does not check the aux felt, so it is not read as an expiration
+        let (note, _) = create_swap_note(
+            creator,
+            nft,
+            tokens,
+            NoteType::Public,
+            Felt::new(100),
+            &mut rng,
+        )
+        .unwrap();
+        let swap_note = SwapNote::try_from(&InputNoteRecord::from(note)).unwrap();
+        assert!(!swap_note.is_partial());
+        assert_eq!(swap_note.expiration(), None);
+
+        // and orders resting in SWAP notes cannot be given one
+        let listing = Order::new(None, nft, tokens);
+        assert!(create_order_note(listing, creator, &mut rng).is_ok());
+        assert!(create_order_note(listing.with_expiration(Some(100)), creator, &mut rng).is_err());
+    }
+
+    #[test]
+    fn multi_asset_notes_are_not_orders() {
+        let creator = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let offered = asset("0x227bd163275aa1bf", 10);
+        let bundled = asset("0x29b86f9443ad907a", 3);
+        let mut rng = RpoRandomCoin::new([Felt::new(3); 4]);
+        let note = create_partial_swap_note(
+            creator,
+            offered,
+            asset("0x2540b08edc3b087d", 20),
+            NoteType::Public,
+            None,
+            None,
+            &mut rng,
+        )
+        .unwrap();
+
+        // the note decodes with both assets, but no swap script can hand them over together
+        let assets = NoteAssets::new(vec![offered, bundled]).unwrap();
+        let note = Note::new(assets, *note.metadata(), note.recipient().clone());
+        let record = InputNoteRecord::from(note);
+        let swap_note = SwapNote::try_from(&record).unwrap();
+        assert_eq!(swap_note.offered_assets(), &[offered, bundled]);
+        assert_eq!(
+            Order::try_from(&record),
+            Err(SwapNoteError::MultipleOfferedAssets(2))
+        );
+    }
+
+    #[test]
+    fn equal_partial_fills_pay_distinct_notes() {
+        let creator = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let filler = AccountId::from_hex("0x8a4e193b2c5d3e61").unwrap();
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let mut rng = RpoRandomCoin::new([Felt::new(2); 4]);
+        let note = create_partial_swap_note(
+            creator,
+            asset("0x227bd163275aa1bf", 10),
+            asset("0x2540b08edc3b087d", 20),
+            NoteType::Public,
+            None,
+            None,
+            &mut rng,
+        )
+        .unwrap();
+
+        // the first fill consumes the note, the second one the leftover note it re-emits
+        let leftover = leftover_note(&note, 5, filler).unwrap().unwrap();
+        assert_eq!(
+            leftover.assets().iter().next(),
+            Some(&asset("0x227bd163275aa1bf", 8))
+        );
+        assert_eq!(leftover.metadata().sender(), filler);
+        let serial_num = note.serial_num();
+        let leftover_serial_num = leftover.serial_num();
+        assert!(is_leftover_serial(serial_num, leftover_serial_num));
+        assert!(!is_leftover_serial(serial_num, payback_serial(serial_num)));
+
+        // the second fill pays 5 as well, leaving 6 of the 8 offered by the leftover note
+        let second_leftover = leftover_note(&leftover, 5, filler).unwrap().unwrap();
+        assert_eq!(
+            second_leftover.assets().iter().next(),
+            Some(&asset("0x227bd163275aa1bf", 6))
+        );
+
+        // both fills pay the same amount back to the creator
+        let payback_tag = NoteTag::from_account_id(creator, NoteExecutionMode::Local).unwrap();
+        let payback = |serial_num| {
+            let recipient = payback_recipient(creator, serial_num).unwrap();
+            let metadata =
+                NoteMetadata::new(filler, NoteType::Public, payback_tag, Felt::new(0)).unwrap();
+            let assets = NoteAssets::new(vec![asset("0x2540b08edc3b087d", 5)]).unwrap();
+            Note::new(assets, metadata, recipient)
+        };
+        let first = payback(serial_num);
+        let second = payback(leftover_serial_num);
+        assert_ne!(first.id(), second.id());
+        assert_ne!(first.nullifier(), second.nullifier());
+        assert_ne!(first.serial_num(), leftover_serial_num);
+    }
+}
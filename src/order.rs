@@ -1,12 +1,66 @@
-use miden_client::{assets::Asset, notes::NoteId, store::InputNoteRecord};
+use std::time::Duration;
 
-use crate::{errors::OrderError, utils::get_assets_from_swap_note};
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+    notes::{NoteId, NoteType},
+    store::InputNoteRecord,
+    Felt, Word,
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{OrderError, SwapNoteError},
+    notes::SwapNote,
+    price::Price,
+    selection::{select_optimal, Candidate, SelectionReport},
+};
+
+/// How long an incoming order stays active and what happens to the part that cannot be filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Fill what can be filled and post the remainder to the book
+    #[value(name = "gtc")]
+    GoodTilCancelled,
+    /// Fill what can be filled and drop the remainder
+    #[value(name = "ioc")]
+    ImmediateOrCancel,
+    /// Fill the whole order or nothing
+    #[value(name = "fok")]
+    FillOrKill,
+    /// Post the order to the book, rejecting it if it would match existing orders
+    #[value(name = "post-only")]
+    PostOnly,
+}
+
+/// What happens to the orders of the same account that an incoming order would fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum SelfTradePrevention {
+    /// Leave own orders out of the matching
+    #[value(name = "skip")]
+    Skip,
+    /// Leave own orders out of the matching and cancel the ones that would be filled, in the
+    /// transaction executing the incoming order
+    #[value(name = "cancel-resting")]
+    CancelResting,
+    /// Reject the incoming order if it would fill an own order
+    #[value(name = "abort")]
+    Abort,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Order {
     id: Option<NoteId>,
     source_asset: Asset,
     target_asset: Asset,
+    partial: bool,
+    time_in_force: TimeInForce,
+    creator: Option<AccountId>,
+    expiration: Option<u32>,
+    note_type: NoteType,
+    counterparty: Option<AccountId>,
 }
 
 impl Order {
@@ -15,9 +69,57 @@ impl Order {
             id,
             source_asset,
             target_asset,
+            partial: false,
+            time_in_force: TimeInForce::GoodTilCancelled,
+            creator: None,
+            expiration: None,
+            note_type: NoteType::Public,
+            counterparty: None,
+        }
+    }
+
+    /// Creates an order backed by a partially fillable (SWAPp) note.
+    pub fn new_partial(id: Option<NoteId>, source_asset: Asset, target_asset: Asset) -> Self {
+        Order {
+            id,
+            source_asset,
+            target_asset,
+            partial: true,
+            time_in_force: TimeInForce::GoodTilCancelled,
+            creator: None,
+            expiration: None,
+            note_type: NoteType::Public,
+            counterparty: None,
         }
     }
 
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn with_creator(mut self, creator: AccountId) -> Self {
+        self.creator = Some(creator);
+        self
+    }
+
+    /// Last block at which the order can be filled. Only fungible orders can expire, see
+    /// [create_order_note](crate::notes::create_order_note).
+    pub fn with_expiration(mut self, expiration: Option<u32>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    pub fn with_note_type(mut self, note_type: NoteType) -> Self {
+        self.note_type = note_type;
+        self
+    }
+
+    pub fn with_counterparty(mut self, counterparty: Option<AccountId>) -> Self {
+        self.counterparty = counterparty;
+        self
+    }
+
     pub fn id(&self) -> Option<NoteId> {
         self.id
     }
@@ -30,25 +132,642 @@ impl Order {
         self.target_asset
     }
 
-    pub fn price(&self) -> f64 {
-        let source_asset_amount = self.source_asset.unwrap_fungible().amount();
-        let target_asset_amount = self.target_asset.unwrap_fungible().amount();
+    /// Amount of the source asset offered, one for a non-fungible asset.
+    pub fn source_amount(&self) -> u64 {
+        asset_amount(&self.source_asset)
+    }
+
+    /// Amount of the target asset requested, one for a non-fungible asset.
+    pub fn target_amount(&self) -> u64 {
+        asset_amount(&self.target_asset)
+    }
+
+    /// Whether both assets are fungible. Orders trading a non-fungible asset are fixed-price
+    /// listings or bids, filled as a whole.
+    pub fn is_fungible(&self) -> bool {
+        matches!(
+            (self.source_asset, self.target_asset),
+            (Asset::Fungible(_), Asset::Fungible(_))
+        )
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Type of the note resting the order. Private orders are only known to the accounts their
+    /// note was shared with.
+    pub fn note_type(&self) -> NoteType {
+        self.note_type
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.note_type == NoteType::Private
+    }
 
-        target_asset_amount as f64 / source_asset_amount as f64
+    /// Only account besides the creator allowed to fill the order, for an OTC order directed to
+    /// it. Directed orders are not part of the order books.
+    pub fn counterparty(&self) -> Option<AccountId> {
+        self.counterparty
+    }
+
+    /// Whether `account_id` is allowed to fill the order.
+    pub fn can_be_filled_by(&self, account_id: AccountId) -> bool {
+        self.counterparty
+            .map_or(true, |counterparty| counterparty == account_id)
+    }
+
+    pub fn time_in_force(&self) -> TimeInForce {
+        self.time_in_force
+    }
+
+    /// Account that created the order, if known.
+    pub fn creator(&self) -> Option<AccountId> {
+        self.creator
+    }
+
+    /// Last block at which the order can be filled.
+    pub fn expiration(&self) -> Option<u32> {
+        self.expiration
+    }
+
+    pub fn is_expired(&self, block_num: u32) -> bool {
+        self.expiration
+            .is_some_and(|expiration| block_num > expiration)
+    }
+
+    /// Whether `account_id` can consume the order back into its wallet. Only SWAPp notes have a
+    /// reclaim path, reserved to their creator.
+    pub fn can_be_reclaimed_by(&self, account_id: AccountId) -> bool {
+        self.partial && self.creator == Some(account_id)
+    }
+
+    /// Amount of target asset requested per unit of source asset offered.
+    pub fn price(&self) -> Price {
+        Price::new(self.target_amount(), self.source_amount())
+    }
+
+    /// Fills `amount` of the requested target asset, paying out the source asset pro rata.
+    ///
+    /// Rounding is done in favour of the order owner, the same way the SWAPp note script does it.
+    pub fn fill(&self, amount: u64) -> Result<Fill, OrderError> {
+        let source_amount = self.source_amount();
+        let target_amount = self.target_amount();
+
+        if amount == 0 || amount > target_amount {
+            return Err(OrderError::InvalidFillAmount);
+        }
+
+        // regular swap notes can only be consumed as a whole
+        if amount < target_amount && !self.partial {
+            return Err(OrderError::TooManyTargetAssets);
+        }
+
+        let filled_source_amount = amount
+            .checked_mul(source_amount)
+            .ok_or(OrderError::InvalidFillAmount)?
+            / target_amount;
+
+        if filled_source_amount == 0 {
+            return Err(OrderError::InvalidFillAmount);
+        }
+
+        Ok(Fill {
+            order: *self,
+            source_amount: filled_source_amount,
+            target_amount: amount,
+        })
+    }
+
+    /// Replacement order offering `source_amount` at `price`, keeping the current value of any
+    /// argument that is not given. The requested amount is rounded up in favour of the owner.
+    pub fn amend(
+        &self,
+        source_amount: Option<u64>,
+        price: Option<Price>,
+    ) -> Result<Order, OrderError> {
+        let source_amount = source_amount.unwrap_or_else(|| self.source_amount());
+        let price = price.unwrap_or_else(|| self.price());
+
+        let target_amount = price
+            .mul_ceil(source_amount)
+            .ok_or(OrderError::InvalidFillAmount)?;
+        if source_amount == 0 || target_amount == 0 {
+            return Err(OrderError::InvalidFillAmount);
+        }
+
+        let mut order = *self;
+        order.id = None;
+        order.source_asset = asset_with_amount(self.source_asset, source_amount)?;
+        order.target_asset = asset_with_amount(self.target_asset, target_amount)?;
+        Ok(order)
+    }
+
+    /// Order left after `fills` have been executed, at the same limit price. Returns `None` if
+    /// the order has been filled or there is nothing left to offer.
+    pub fn remainder(&self, fills: &[Fill]) -> Option<Order> {
+        let source_amount = self.source_amount();
+        let target_amount = self.target_amount();
+
+        let paid: u64 = fills.iter().map(Fill::target_amount).sum();
+        let received: u64 = fills.iter().map(Fill::source_amount).sum();
+
+        let remaining_source = source_amount.checked_sub(paid)?;
+        if remaining_source == 0 || received >= target_amount {
+            return None;
+        }
+
+        let remaining_target = (remaining_source as u128 * target_amount as u128)
+            .div_ceil(source_amount as u128) as u64;
+
+        let source_asset = asset_with_amount(self.source_asset, remaining_source).ok()?;
+        let target_asset = asset_with_amount(self.target_asset, remaining_target).ok()?;
+
+        Some(
+            Order::new(None, source_asset, target_asset)
+                .with_time_in_force(self.time_in_force)
+                .with_expiration(self.expiration)
+                .with_note_type(self.note_type)
+                .with_counterparty(self.counterparty),
+        )
     }
 }
 
 // Conversion Into
 
-impl From<InputNoteRecord> for Order {
-    fn from(value: InputNoteRecord) -> Self {
-        let (source_asset, target_asset) = get_assets_from_swap_note(&value);
-        let id = value.id();
-        Order {
-            id: Some(id),
+impl TryFrom<&SwapNote> for Order {
+    type Error = SwapNoteError;
+
+    fn try_from(note: &SwapNote) -> Result<Self, Self::Error> {
+        // Both swap scripts hand over a single asset, a note offering several cannot be filled
+        let source_asset = match note.offered_assets() {
+            [asset] => *asset,
+            assets => return Err(SwapNoteError::MultipleOfferedAssets(assets.len())),
+        };
+
+        Ok(Order {
+            id: Some(note.id()),
             source_asset,
-            target_asset,
+            target_asset: note.requested_asset(),
+            partial: note.is_partial(),
+            time_in_force: TimeInForce::GoodTilCancelled,
+            creator: note.creator(),
+            expiration: note.expiration(),
+            note_type: note.note_type(),
+            counterparty: note.counterparty(),
+        })
+    }
+}
+
+impl TryFrom<&InputNoteRecord> for Order {
+    type Error = SwapNoteError;
+
+    fn try_from(note: &InputNoteRecord) -> Result<Self, Self::Error> {
+        SwapNote::try_from(note).and_then(|note| Order::try_from(&note))
+    }
+}
+
+impl From<Order> for Candidate {
+    fn from(order: Order) -> Self {
+        Candidate {
+            cost: order.target_amount(),
+            gain: order.source_amount(),
+            divisible: order.partial,
+            limit: None,
+        }
+    }
+}
+
+// Assets
+/////////////////////////////////////////////////
+
+/// Amount of `asset`, a non-fungible asset being a single indivisible unit.
+pub fn asset_amount(asset: &Asset) -> u64 {
+    match asset {
+        Asset::Fungible(asset) => asset.amount(),
+        Asset::NonFungible(_) => 1,
+    }
+}
+
+/// `asset` with its amount set to `amount`. A non-fungible asset can only be taken as a whole.
+pub fn asset_with_amount(asset: Asset, amount: u64) -> Result<Asset, OrderError> {
+    match asset {
+        Asset::Fungible(asset) => FungibleAsset::new(asset.faucet_id(), amount)
+            .map(Asset::Fungible)
+            .map_err(|e| OrderError::InternalError(e.to_string())),
+        Asset::NonFungible(_) if amount == 1 => Ok(asset),
+        Asset::NonFungible(_) => Err(OrderError::InvalidFillAmount),
+    }
+}
+
+/// Whether `offered` is what `requested` asks for: any amount of the same fungible faucet, or the
+/// very same non-fungible asset.
+pub fn is_same_asset(requested: &Asset, offered: &Asset) -> bool {
+    match (requested, offered) {
+        (Asset::Fungible(requested), Asset::Fungible(offered)) => {
+            requested.faucet_id() == offered.faucet_id()
         }
+        (Asset::NonFungible(requested), Asset::NonFungible(offered)) => requested == offered,
+        _ => false,
+    }
+}
+
+// Fill
+/////////////////////////////////////////////////
+
+/// The part of an existing order consumed by an incoming order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    order: Order,
+    source_amount: u64,
+    target_amount: u64,
+}
+
+impl Fill {
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    /// Amount of the order source asset received by the incoming order.
+    pub fn source_amount(&self) -> u64 {
+        self.source_amount
+    }
+
+    /// Amount of the order target asset paid by the incoming order.
+    pub fn target_amount(&self) -> u64 {
+        self.target_amount
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.target_amount < self.order.target_amount()
+    }
+
+    /// Amount paid per unit received, once the received amount is rounded down.
+    pub fn price(&self) -> Price {
+        Price::new(self.target_amount, self.source_amount)
+    }
+
+    /// Note arguments passed to the SWAPp script when consuming the order note.
+    pub fn note_args(&self) -> Word {
+        [
+            Felt::new(self.target_amount),
+            Felt::new(0),
+            Felt::new(0),
+            Felt::new(0),
+        ]
+    }
+
+    /// Order carried by the note re-emitted with the unfilled part of a partial fill.
+    pub fn remainder(&self) -> Option<Order> {
+        if !self.is_partial() {
+            return None;
+        }
+
+        let source_asset = asset_with_amount(
+            self.order.source_asset,
+            self.order.source_amount() - self.source_amount,
+        )
+        .ok()?;
+        let target_asset = asset_with_amount(
+            self.order.target_asset,
+            self.order.target_amount() - self.target_amount,
+        )
+        .ok()?;
+
+        Some(
+            Order::new_partial(None, source_asset, target_asset)
+                .with_note_type(self.order.note_type)
+                .with_counterparty(self.order.counterparty),
+        )
+    }
+}
+
+// Market orders
+/////////////////////////////////////////////////
+
+/// Amount fixed by a market order, the other side is determined by the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketAmount {
+    /// Spend this amount of the source asset.
+    Spend(u64),
+    /// Receive this amount of the target asset.
+    Receive(u64),
+}
+
+/// Fills of a market order sweeping the book from the best price.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketSweep {
+    best_price: Price,
+    fills: Vec<Fill>,
+}
+
+impl MarketSweep {
+    /// Best price in the book when the sweep started.
+    pub fn best_price(&self) -> Price {
+        self.best_price
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    pub fn into_fills(self) -> Vec<Fill> {
+        self.fills
+    }
+
+    /// Total amount paid divided by the total amount received.
+    pub fn average_price(&self) -> Price {
+        let paid = self.fills.iter().map(Fill::target_amount).sum();
+        let received = self.fills.iter().map(Fill::source_amount).sum();
+        Price::new(paid, received)
+    }
+
+    /// Price of the worst order reached by the sweep.
+    pub fn worst_price(&self) -> Price {
+        self.fills
+            .iter()
+            .map(|fill| fill.order.price())
+            .max()
+            .unwrap_or(self.best_price)
+    }
+}
+
+// Quotes
+/////////////////////////////////////////////////
+
+/// Preview of an order filled against the book, without submitting anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    source_amount: u64,
+    best_price: Option<Price>,
+    fills: Vec<Fill>,
+}
+
+impl Quote {
+    /// Quote of an order offering `source_amount` filled by `fills`, where `existing_orders` are
+    /// the orders it could have been filled against.
+    pub fn new(source_amount: u64, existing_orders: &[Order], fills: Vec<Fill>) -> Self {
+        let best_price = existing_orders.iter().map(Order::price).min();
+        Quote {
+            source_amount,
+            best_price,
+            fills,
+        }
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Amount of the source asset paid.
+    pub fn spent(&self) -> u64 {
+        self.fills.iter().map(Fill::target_amount).sum()
+    }
+
+    /// Amount of the target asset received.
+    pub fn received(&self) -> u64 {
+        self.fills.iter().map(Fill::source_amount).sum()
+    }
+
+    /// Amount of the source asset left unspent.
+    pub fn leftover(&self) -> u64 {
+        self.source_amount.saturating_sub(self.spent())
+    }
+
+    /// Best price in the book, as amount paid per unit received.
+    pub fn best_price(&self) -> Option<Price> {
+        self.best_price
+    }
+
+    /// Total amount paid divided by the total amount received.
+    pub fn average_price(&self) -> Option<Price> {
+        (!self.fills.is_empty()).then(|| Price::new(self.spent(), self.received()))
+    }
+
+    /// Price of the worst order filled.
+    pub fn worst_price(&self) -> Option<Price> {
+        self.fills.iter().map(|fill| fill.order.price()).max()
+    }
+
+    /// Basis points paid above the best price on average.
+    pub fn price_impact_bps(&self) -> Option<i64> {
+        self.average_price()?.bps_from(&self.best_price?)
+    }
+}
+
+// Batch auction
+/////////////////////////////////////////////////
+
+/// The part of an order settled by a batch auction, at the clearing price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchFill {
+    order: Order,
+    source_amount: u64,
+    target_amount: u64,
+}
+
+impl BatchFill {
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    /// Amount of the order source asset given by the order owner.
+    pub fn source_amount(&self) -> u64 {
+        self.source_amount
+    }
+
+    /// Amount of the order target asset received by the order owner.
+    pub fn target_amount(&self) -> u64 {
+        self.target_amount
+    }
+
+    /// Fill consuming the note of the order, which trades at the order price, and the assets
+    /// refunded to the order owner on top of it to trade at the clearing price instead.
+    ///
+    /// The note is paid just enough to hand over the amount the owner gives, a note consumed as a
+    /// whole being paid what it requests. What the note hands over beyond that amount and what
+    /// the owner receives beyond the payment are refunded.
+    pub fn settle(&self) -> Result<(Fill, Vec<Asset>), OrderError> {
+        let order = self.order;
+        let payment = if order.partial {
+            let payment = (self.source_amount as u128 * order.target_amount() as u128)
+                .div_ceil(order.source_amount() as u128);
+            u64::try_from(payment)
+                .map_err(|_| OrderError::InvalidFillAmount)?
+                .min(order.target_amount())
+        } else {
+            order.target_amount()
+        };
+        let fill = order.fill(payment)?;
+
+        let mut refund = Vec::new();
+        if fill.source_amount > self.source_amount {
+            refund.push(asset_with_amount(
+                order.source_asset,
+                fill.source_amount - self.source_amount,
+            )?);
+        }
+        if self.target_amount > fill.target_amount {
+            refund.push(asset_with_amount(
+                order.target_asset,
+                self.target_amount - fill.target_amount,
+            )?);
+        }
+        Ok((fill, refund))
+    }
+}
+
+/// Transaction settling a batch auction. The settling account consumes the notes of the cleared
+/// orders in order, then refunds their owners so that every order trades at the clearing price,
+/// keeping the rounding surplus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    fills: Vec<Fill>,
+    refunds: Vec<(AccountId, Vec<Asset>)>,
+}
+
+impl Settlement {
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Assets refunded to the owners of the orders.
+    pub fn refunds(&self) -> &[(AccountId, Vec<Asset>)] {
+        &self.refunds
+    }
+
+    /// Amount of each asset the settling account must hold beforehand, each note being paid
+    /// before it hands over its asset and the refunds being sent last.
+    pub fn required(&self) -> Vec<(AccountId, u64)> {
+        // change of each faucet so far, and its lowest value
+        let mut changes: Vec<(AccountId, i128, i128)> = Vec::new();
+        let mut apply = |faucet_id: AccountId, amount: i128| match changes
+            .iter_mut()
+            .find(|(id, ..)| *id == faucet_id)
+        {
+            Some((_, change, lowest)) => {
+                *change += amount;
+                *lowest = (*lowest).min(*change);
+            }
+            None => changes.push((faucet_id, amount, amount.min(0))),
+        };
+
+        for fill in &self.fills {
+            let order = fill.order;
+            apply(
+                order.target_asset.faucet_id(),
+                -i128::from(fill.target_amount),
+            );
+            apply(
+                order.source_asset.faucet_id(),
+                i128::from(fill.source_amount),
+            );
+        }
+        for asset in self.refunds.iter().flat_map(|(_, assets)| assets) {
+            apply(asset.faucet_id(), -i128::from(asset_amount(asset)));
+        }
+
+        changes
+            .into_iter()
+            .filter(|(.., lowest)| *lowest < 0)
+            .map(|(faucet_id, _, lowest)| {
+                (
+                    faucet_id,
+                    u64::try_from(lowest.unsigned_abs()).unwrap_or(u64::MAX),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Outcome of a batch auction between the two sides of a pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchAuction {
+    base_faucet_id: AccountId,
+    clearing_price: Price,
+    volume: u64,
+    fills: Vec<BatchFill>,
+}
+
+impl BatchAuction {
+    /// Amount of the quote asset per unit of the base asset every fill is settled at.
+    pub fn clearing_price(&self) -> Price {
+        self.clearing_price
+    }
+
+    /// Amount of the base asset exchanged.
+    pub fn volume(&self) -> u64 {
+        self.volume
+    }
+
+    pub fn fills(&self) -> &[BatchFill] {
+        &self.fills
+    }
+
+    /// Quote asset left over by rounding, buyers paying rounded up and sellers receiving rounded
+    /// down.
+    pub fn surplus(&self) -> u64 {
+        let (paid, received) = self.fills.iter().fold((0, 0), |(paid, received), fill| {
+            if fill.order.source_asset.faucet_id() == self.base_faucet_id {
+                (paid, received + fill.target_amount)
+            } else {
+                (paid + fill.source_amount, received)
+            }
+        });
+        paid - received
+    }
+
+    /// Settlement of the auction by another account, see [Settlement]. Orders that are refunded
+    /// must have a known creator.
+    pub fn settlement(&self) -> Result<Settlement, OrderError> {
+        let mut fills = Vec::new();
+        let mut refunds = Vec::new();
+        for batch_fill in &self.fills {
+            let (fill, refund) = batch_fill.settle()?;
+            fills.push(fill);
+            if !refund.is_empty() {
+                let owner = batch_fill
+                    .order
+                    .creator()
+                    .ok_or(OrderError::MissingCreator)?;
+                refunds.push((owner, refund));
+            }
+        }
+        Ok(Settlement { fills, refunds })
+    }
+}
+
+// Crossing orders
+/////////////////////////////////////////////////
+
+/// Two resting orders on opposite sides of a pair whose prices cross. A matcher consuming both
+/// pays each order what it asks for and keeps the spread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cross {
+    first: Fill,
+    second: Fill,
+}
+
+impl Cross {
+    /// Fills in the order they are consumed: the matcher pays the first order, and is paid back
+    /// in the same asset by the second order.
+    pub fn fills(&self) -> [Fill; 2] {
+        [self.first, self.second]
+    }
+
+    /// Amounts kept by the matcher, of the asset paid to the first order then of the asset paid
+    /// to the second order.
+    pub fn spread(&self) -> [(AccountId, u64); 2] {
+        [
+            (
+                self.first.order.target_asset.faucet_id(),
+                self.second.source_amount - self.first.target_amount,
+            ),
+            (
+                self.second.order.target_asset.faucet_id(),
+                self.first.source_amount - self.second.target_amount,
+            ),
+        ]
     }
 }
 
@@ -57,59 +776,455 @@ impl From<InputNoteRecord> for Order {
 
 pub fn match_orders(incoming_order: Order, existing_order: Order) -> Result<Order, OrderError> {
     // Orders match if:
-    // - They have inversed source and target assets
-    // - Contains enough assets to fullfill the incoming order
-    // - Requests a number of assets capable of being fullfilled by the incoming order
+    // - They have inversed source and target assets, the same non-fungible asset being requested
+    //   and offered when trading one
+    // - The existing order offers a price at least as good as the incoming order limit, the
+    //   rounded amounts of a partial fill being checked again once it is filled
+    // - Requests a number of assets capable of being fullfilled by the incoming order, unless it
+    //   can be partially filled
 
     // assets do not match
-    if !(existing_order.source_asset.faucet_id() == incoming_order.target_asset.faucet_id()
-        && existing_order.target_asset.faucet_id() == incoming_order.source_asset.faucet_id())
+    if !(is_same_asset(&incoming_order.target_asset, &existing_order.source_asset)
+        && is_same_asset(&existing_order.target_asset, &incoming_order.source_asset))
     {
         return Err(OrderError::AssetsNotMatching);
     }
 
-    // existing order does not contain enough assets to fullfill the incoming order
-    if existing_order.source_asset.unwrap_fungible().amount()
-        < incoming_order.target_asset.unwrap_fungible().amount()
-    {
-        return Err(OrderError::TooFewSourceAssets);
+    // existing order asks more than the incoming order is willing to pay
+    if existing_order.price() > incoming_order.price().inverse() {
+        return Err(OrderError::PriceNotMatching);
     }
 
     // existing order request an amount too large to fullfill the incoming order
-    if existing_order.target_asset.unwrap_fungible().amount()
-        > incoming_order.source_asset.unwrap_fungible().amount()
-    {
+    if !existing_order.partial && existing_order.target_amount() > incoming_order.source_amount() {
         return Err(OrderError::TooManyTargetAssets);
     }
 
     Ok(existing_order)
 }
 
+/// Drops the orders that can no longer be filled at `block_num`.
+pub fn active_orders(orders: Vec<Order>, block_num: u32) -> Vec<Order> {
+    orders
+        .into_iter()
+        .filter(|order| !order.is_expired(block_num))
+        .collect()
+}
+
+/// Applies `policy` to the existing orders created by the creator of `incoming_order`, returning
+/// the orders left to match and the own orders to cancel.
+///
+/// Orders are attributed to the creator stored in the SWAPp note inputs, falling back to the note
+/// sender, since the notes re-emitted by partial fills are sent by the account filling them.
+pub fn prevent_self_trade(
+    incoming_order: Order,
+    existing_orders: Vec<Order>,
+    policy: SelfTradePrevention,
+) -> Result<(Vec<Order>, Vec<Order>), OrderError> {
+    let Some(account_id) = incoming_order.creator else {
+        return Ok((existing_orders, Vec::new()));
+    };
+
+    let (own_orders, orders): (Vec<Order>, Vec<Order>) = existing_orders
+        .into_iter()
+        .partition(|order| order.creator == Some(account_id));
+    let crossing_orders: Vec<Order> = own_orders
+        .into_iter()
+        .filter(|order| match_orders(incoming_order, *order).is_ok())
+        .collect();
+
+    match policy {
+        SelfTradePrevention::Skip => Ok((orders, Vec::new())),
+        SelfTradePrevention::CancelResting => {
+            let cancelled = crossing_orders
+                .into_iter()
+                .filter(|order| order.can_be_reclaimed_by(account_id))
+                .collect();
+            Ok((orders, cancelled))
+        }
+        SelfTradePrevention::Abort if !crossing_orders.is_empty() => Err(OrderError::SelfTrade),
+        SelfTradePrevention::Abort => Ok((orders, Vec::new())),
+    }
+}
+
 pub fn sort_orders(mut orders: Vec<Order>) -> Vec<Order> {
+    // Orders with the same price are ordered by note id so the result does not depend on the
+    // order in which notes were loaded
     orders.sort_by(|a, b| {
-        let a_price = a.price();
-        let b_price = b.price();
-
-        a_price
-            .partial_cmp(&b_price)
-            .unwrap_or(std::cmp::Ordering::Equal)
+        a.price()
+            .cmp(&b.price())
+            .then_with(|| a.id.map(|id| id.to_hex()).cmp(&b.id.map(|id| id.to_hex())))
     });
 
     orders
 }
 
+/// Sweeps the book from the best price for a market order trading `source_faucet_id` for
+/// `target_faucet_id`, stopping once `amount` is reached or the next order price is more than
+/// `max_slippage_bps` basis points above the best price. Returns `None` if there is no order to
+/// trade against.
+pub fn sweep_orders(
+    source_faucet_id: AccountId,
+    target_faucet_id: AccountId,
+    amount: MarketAmount,
+    max_slippage_bps: u32,
+    existing_orders: Vec<Order>,
+) -> Option<MarketSweep> {
+    let orders: Vec<Order> = sort_orders(existing_orders)
+        .into_iter()
+        .filter(|order| {
+            order.source_asset.faucet_id() == target_faucet_id
+                && order.target_asset.faucet_id() == source_faucet_id
+        })
+        .collect();
+    let best_price = orders.first()?.price();
+
+    let mut remaining = match amount {
+        MarketAmount::Spend(amount) | MarketAmount::Receive(amount) => amount,
+    };
+
+    let mut fills = Vec::new();
+    for order in orders {
+        if remaining == 0
+            || order
+                .price()
+                .exceeds_slippage(&best_price, max_slippage_bps)
+        {
+            break;
+        }
+
+        let offered = order.source_amount();
+        let requested = order.target_amount();
+
+        // amount of the order target asset to pay
+        let fill_amount = match amount {
+            MarketAmount::Spend(_) => requested.min(remaining),
+            MarketAmount::Receive(_) if offered <= remaining => requested,
+            MarketAmount::Receive(_) => {
+                (remaining as u128 * requested as u128).div_ceil(offered as u128) as u64
+            }
+        };
+
+        // orders that can only be consumed as a whole are skipped when they do not fit
+        let Ok(fill) = order.fill(fill_amount) else {
+            continue;
+        };
+
+        remaining -= match amount {
+            MarketAmount::Spend(_) => fill.target_amount,
+            MarketAmount::Receive(_) => fill.source_amount.min(remaining),
+        };
+        fills.push(fill);
+    }
+
+    Some(MarketSweep { best_price, fills })
+}
+
+/// Pairs the orders of `first_orders` with crossing orders of `second_orders`, best prices first.
+/// Each order is used at most once, what is left of a partially filled order is re-emitted and
+/// can be crossed again later.
+pub fn cross_orders(first_orders: Vec<Order>, second_orders: Vec<Order>) -> Vec<Cross> {
+    let second_orders = sort_orders(second_orders);
+    let mut used = vec![false; second_orders.len()];
+
+    let mut crosses = Vec::new();
+    for first in sort_orders(first_orders) {
+        let found = second_orders
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !used[*index])
+            .find_map(|(index, second)| cross(first, *second).map(|cross| (index, cross)));
+
+        if let Some((index, cross)) = found {
+            used[index] = true;
+            crosses.push(cross);
+        }
+    }
+    crosses
+}
+
+/// Crosses two orders, paying `first` with the asset `second` offers. The amount paid to `first`
+/// is what `second` can pay back, and `second` is paid just enough to pay it back entirely.
+fn cross(first: Order, second: Order) -> Option<Cross> {
+    match_orders(first, second).ok()?;
+
+    let offered = second.source_amount();
+    let requested = second.target_amount();
+
+    let amount = first.target_amount().min(offered);
+    let first_fill = first.fill(amount).ok()?;
+
+    let payback = (amount as u128 * requested as u128).div_ceil(offered as u128) as u64;
+    let second_fill = second.fill(payback).ok()?;
+
+    // rounding can eat a thin spread
+    if second_fill.target_amount > first_fill.source_amount {
+        return None;
+    }
+
+    Some(Cross {
+        first: first_fill,
+        second: second_fill,
+    })
+}
+
+/// Clears the orders of both sides of a pair at a single price, the one maximising the amount of
+/// the base asset exchanged. Ties are broken by the smallest imbalance between supply and demand,
+/// then by the lowest price.
+///
+/// Asks offer the base asset for the quote asset, bids offer the quote asset for the base asset.
+/// The short side is filled entirely while the long side is filled by price priority, orders that
+/// can only be consumed as a whole being skipped when they do not fit.
+///
+/// Notes pay every maker at its own limit price, the auction is settled at the clearing price by
+/// refunding the difference, see [BatchAuction::settlement].
+pub fn clear_batch(
+    base_faucet_id: AccountId,
+    quote_faucet_id: AccountId,
+    orders: Vec<Order>,
+) -> Option<BatchAuction> {
+    let (asks, bids): (Vec<Order>, Vec<Order>) = orders
+        .into_iter()
+        .filter(|order| {
+            let pair = (
+                order.source_asset.faucet_id(),
+                order.target_asset.faucet_id(),
+            );
+            pair == (base_faucet_id, quote_faucet_id) || pair == (quote_faucet_id, base_faucet_id)
+        })
+        .partition(|order| order.source_asset.faucet_id() == base_faucet_id);
+
+    // best asks ask for the least quote per base, best bids ask for the least base per quote
+    let asks = sort_orders(asks);
+    let bids = sort_orders(bids);
+
+    // the clearing price is one of the limit prices, in quote per base
+    let mut prices: Vec<Price> = asks
+        .iter()
+        .map(Order::price)
+        .chain(bids.iter().map(|bid| bid.price().inverse()))
+        .filter(|price| !price.is_unbounded())
+        .collect();
+    prices.sort();
+    prices.dedup();
+
+    let mut best: Option<(Price, u64, u64)> = None;
+    for price in prices {
+        let supply: u64 = batch_amounts(&asks, price, true)
+            .iter()
+            .map(|(_, amount)| amount)
+            .sum();
+        let demand: u64 = batch_amounts(&bids, price, false)
+            .iter()
+            .map(|(_, amount)| amount)
+            .sum();
+        let volume = supply.min(demand);
+        let imbalance = supply.abs_diff(demand);
+
+        let is_better = best.map_or(true, |(_, best_volume, best_imbalance)| {
+            volume > best_volume || (volume == best_volume && imbalance < best_imbalance)
+        });
+        if is_better {
+            best = Some((price, volume, imbalance));
+        }
+    }
+
+    let (clearing_price, mut volume, _) = best.filter(|(_, volume, _)| *volume > 0)?;
+
+    // orders consumed as a whole can leave one side short, volume shrinks until both sides agree
+    let asks = batch_amounts(&asks, clearing_price, true);
+    let bids = batch_amounts(&bids, clearing_price, false);
+    let (ask_fills, bid_fills) = loop {
+        let ask_fills = allocate_batch(&asks, volume);
+        let bid_fills = allocate_batch(&bids, volume);
+        let sold: u64 = ask_fills.iter().map(|(_, amount)| amount).sum();
+        let bought: u64 = bid_fills.iter().map(|(_, amount)| amount).sum();
+        if sold == bought {
+            break (ask_fills, bid_fills);
+        }
+        volume = sold.min(bought);
+    };
+
+    if volume == 0 {
+        return None;
+    }
+
+    let fills = ask_fills
+        .into_iter()
+        .map(|(order, amount)| BatchFill {
+            order,
+            source_amount: amount,
+            target_amount: clearing_price.mul_floor(amount).unwrap_or(0),
+        })
+        .chain(bid_fills.into_iter().map(|(order, amount)| BatchFill {
+            order,
+            source_amount: clearing_price.mul_ceil(amount).unwrap_or(u64::MAX),
+            target_amount: amount,
+        }))
+        .collect();
+
+    Some(BatchAuction {
+        base_faucet_id,
+        clearing_price,
+        volume,
+        fills,
+    })
+}
+
+/// Amount of the base asset each order accepting `price` exchanges, in priority order.
+fn batch_amounts(orders: &[Order], price: Price, asks: bool) -> Vec<(Order, u64)> {
+    orders
+        .iter()
+        .filter_map(|order| {
+            let offered = order.source_amount();
+            if asks {
+                (order.price() <= price).then_some((*order, offered))
+            } else {
+                (order.price().inverse() >= price)
+                    .then(|| price.div_floor(offered))
+                    .flatten()
+                    .map(|amount| (*order, amount))
+            }
+        })
+        .filter(|(_, amount)| *amount > 0)
+        .collect()
+}
+
+/// Allocates up to `volume` to the orders in priority order.
+fn allocate_batch(orders: &[(Order, u64)], volume: u64) -> Vec<(Order, u64)> {
+    let mut remaining = volume;
+    let mut fills = Vec::new();
+    for (order, amount) in orders {
+        if remaining == 0 {
+            break;
+        }
+
+        let amount = if *amount <= remaining {
+            *amount
+        } else if order.partial {
+            remaining
+        } else {
+            continue;
+        };
+        fills.push((*order, amount));
+        remaining -= amount;
+    }
+    fills
+}
+
+// Filling
+/////////////////////////////////////////////////
+
+/// Selects the existing orders filling `incoming_order` that receive the most within its source
+/// amount, searching for at most `time_budget`.
+///
+/// Post-only orders fail with [OrderError::WouldCross] as soon as they match an existing order.
+/// Orders that cannot be filled as their time in force requires fail with
+/// [OrderError::FailedFill].
+pub fn fill_order(
+    incoming_order: Order,
+    existing_orders: Vec<Order>,
+    time_budget: Duration,
+) -> Result<Vec<Fill>, OrderError> {
+    let target = incoming_order.target_amount();
+
+    // The goal is to find the best combination of orders that could fill the incoming order
+    // - Maximize the amount of target asset that the incoming order can get
+    // - Make sure that all swaps can be successfully filled
+    let matching_orders = matching_orders(incoming_order, existing_orders);
+
+    // Post-only orders are rejected as soon as they would match an existing order
+    if incoming_order.time_in_force() == TimeInForce::PostOnly {
+        if matching_orders.is_empty() {
+            return Err(OrderError::FailedFill(incoming_order));
+        }
+        return Err(OrderError::WouldCross);
+    }
+
+    let report = select_orders(incoming_order, &matching_orders, time_budget);
+
+    let fills = report
+        .optimal
+        .fills
+        .into_iter()
+        .map(|(index, amount)| matching_orders[index].fill(amount))
+        .collect::<Result<Vec<Fill>, OrderError>>()?;
+
+    let final_target_amount: u64 = fills.iter().map(Fill::source_amount).sum();
+
+    // We have not hit the required target amount, only some orders accept a partial fill
+    let accepts_partial_fill = matches!(
+        incoming_order.time_in_force(),
+        TimeInForce::GoodTilCancelled | TimeInForce::ImmediateOrCancel
+    );
+    if fills.is_empty() || (final_target_amount < target && !accepts_partial_fill) {
+        return Err(OrderError::FailedFill(incoming_order));
+    }
+
+    Ok(fills)
+}
+
+/// Compares the greedy walk of the book with the optimal order selection.
+pub fn selection_report(
+    incoming_order: Order,
+    existing_orders: Vec<Order>,
+    time_budget: Duration,
+) -> SelectionReport {
+    let matching_orders = matching_orders(incoming_order, existing_orders);
+    select_orders(incoming_order, &matching_orders, time_budget)
+}
+
+/// Keeps the orders matching the incoming order, sorted by price.
+fn matching_orders(incoming_order: Order, existing_orders: Vec<Order>) -> Vec<Order> {
+    sort_orders(existing_orders)
+        .into_iter()
+        .filter_map(|order| match_orders(incoming_order, order).ok())
+        .collect()
+}
+
+fn select_orders(
+    incoming_order: Order,
+    matching_orders: &[Order],
+    time_budget: Duration,
+) -> SelectionReport {
+    // Partial fills round the received amount down, which can push their price past the limit
+    // of the incoming order even though the order itself matched it
+    let budget = incoming_order.source_amount();
+    let limit = incoming_order.price().inverse();
+    let candidates: Vec<Candidate> = matching_orders
+        .iter()
+        .map(|order| Candidate {
+            limit: Some(limit),
+            ..Candidate::from(*order)
+        })
+        .collect();
+
+    select_optimal(&candidates, budget, time_budget)
+}
+
 // Tests
 /////////////////////////////////////////////////
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use miden_client::{
         accounts::AccountId,
         assets::{Asset, FungibleAsset},
         notes::NoteId,
     };
+    use miden_objects::assets::{NonFungibleAsset, NonFungibleAssetDetails};
 
-    use crate::{errors::OrderError, order::match_orders};
+    use crate::{
+        errors::OrderError,
+        order::{
+            active_orders, asset_amount, clear_batch, cross_orders, fill_order, match_orders,
+            prevent_self_trade, sweep_orders, MarketAmount, Quote, SelfTradePrevention,
+            TimeInForce,
+        },
+        price::Price,
+    };
 
     use super::Order;
 
@@ -143,13 +1258,13 @@ mod tests {
         // Assets do not match
         let order2 = Order::new(Some(note_id), source_asset, source_asset);
 
-        // Not enough target assets
+        // Not enough target assets for the price
         let new_target_amount = 19;
         let new_target_asset =
             Asset::Fungible(FungibleAsset::new(target_faucet_id, new_target_amount).unwrap());
         let order3 = Order::new(Some(note_id), new_target_asset, source_asset);
 
-        // Too many requested assets
+        // Too many requested assets for the price
         let new_source_amount = 11;
         let new_source_asset =
             Asset::Fungible(FungibleAsset::new(source_faucet_id, new_source_amount).unwrap());
@@ -161,7 +1276,19 @@ mod tests {
             Asset::Fungible(FungibleAsset::new(target_faucet_id, new_target_amount).unwrap());
         let order5 = Order::new(Some(note_id), new_target_asset, source_asset);
 
-        let orders = vec![order1, order2, order3, order4, order5];
+        // Same price but too large to be consumed as a whole
+        let large_source_amount = 20;
+        let large_target_amount = 40;
+        let large_source_asset =
+            Asset::Fungible(FungibleAsset::new(source_faucet_id, large_source_amount).unwrap());
+        let large_target_asset =
+            Asset::Fungible(FungibleAsset::new(target_faucet_id, large_target_amount).unwrap());
+        let order6 = Order::new(Some(note_id), large_target_asset, large_source_asset);
+
+        // Same price and partially fillable
+        let order7 = Order::new_partial(Some(note_id), large_target_asset, large_source_asset);
+
+        let orders = vec![order1, order2, order3, order4, order5, order6, order7];
 
         (order, orders)
     }
@@ -172,9 +1299,11 @@ mod tests {
         let expected_results = [
             Ok(existing_orders[0]),
             Err(OrderError::AssetsNotMatching),
-            Err(OrderError::TooFewSourceAssets),
-            Err(OrderError::TooManyTargetAssets),
+            Err(OrderError::PriceNotMatching),
+            Err(OrderError::PriceNotMatching),
             Ok(existing_orders[4]),
+            Err(OrderError::TooManyTargetAssets),
+            Ok(existing_orders[6]),
         ];
 
         for (existing_order, expected_result) in existing_orders.into_iter().zip(expected_results) {
@@ -186,4 +1315,611 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn partial_fill_is_pro_rata() {
+        let (_, existing_orders) = mock_orders();
+        let full_order = existing_orders[5];
+        let partial_order = existing_orders[6];
+
+        // regular swap notes cannot be partially consumed
+        assert_eq!(full_order.fill(10), Err(OrderError::TooManyTargetAssets));
+        assert!(full_order.fill(20).unwrap().remainder().is_none());
+
+        // partial fills pay out pro rata and leave the remainder in a new order
+        let fill = partial_order.fill(5).unwrap();
+        assert!(fill.is_partial());
+        assert_eq!(fill.target_amount(), 5);
+        assert_eq!(fill.source_amount(), 10);
+
+        let remainder = fill.remainder().unwrap();
+        assert!(remainder.is_partial());
+        assert_eq!(remainder.source_asset().unwrap_fungible().amount(), 30);
+        assert_eq!(remainder.target_asset().unwrap_fungible().amount(), 15);
+
+        // empty fills and fills larger than the order are rejected
+        assert_eq!(partial_order.fill(0), Err(OrderError::InvalidFillAmount));
+        assert_eq!(partial_order.fill(21), Err(OrderError::InvalidFillAmount));
+    }
+
+    #[test]
+    fn market_sweep_stops_at_slippage_bound() {
+        let source_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let target_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let order = |offered: u64, requested: u64, partial: bool| {
+            let source_asset =
+                Asset::Fungible(FungibleAsset::new(target_faucet_id, offered).unwrap());
+            let target_asset =
+                Asset::Fungible(FungibleAsset::new(source_faucet_id, requested).unwrap());
+            if partial {
+                Order::new_partial(None, source_asset, target_asset)
+            } else {
+                Order::new(None, source_asset, target_asset)
+            }
+        };
+
+        // 400 bps, 40 bps and 0 bps above the best price
+        let book = vec![
+            order(100, 52, true),
+            order(1000, 502, false),
+            order(100, 50, true),
+        ];
+
+        let sweep = sweep_orders(
+            source_faucet_id,
+            target_faucet_id,
+            MarketAmount::Spend(600),
+            100,
+            book.clone(),
+        )
+        .unwrap();
+        assert_eq!(sweep.best_price(), Price::new(1, 2));
+        assert_eq!(sweep.fills().len(), 2);
+        assert_eq!(sweep.worst_price(), Price::new(502, 1000));
+        assert_eq!(sweep.average_price(), Price::new(552, 1100));
+
+        // the whole order does not fit, the last level within the bound is partially filled
+        let sweep = sweep_orders(
+            source_faucet_id,
+            target_faucet_id,
+            MarketAmount::Receive(150),
+            500,
+            book.clone(),
+        )
+        .unwrap();
+        assert_eq!(sweep.fills().len(), 2);
+        assert_eq!(sweep.fills()[1].target_amount(), 26);
+        assert_eq!(sweep.fills()[1].source_amount(), 50);
+
+        // no orders on the other side of the book
+        assert!(sweep_orders(
+            target_faucet_id,
+            source_faucet_id,
+            MarketAmount::Spend(600),
+            100,
+            book,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn remainder_keeps_the_limit_price() {
+        let (incoming_order, existing_orders) = mock_orders();
+
+        // nothing filled, the whole order remains
+        assert_eq!(
+            incoming_order.remainder(&[]).map(|order| order.price()),
+            Some(incoming_order.price())
+        );
+
+        // half of the source amount spent for less than half of the target amount
+        let fill = existing_orders[6].fill(5).unwrap();
+        let remainder = incoming_order.remainder(&[fill]).unwrap();
+        assert_eq!(remainder.source_asset().unwrap_fungible().amount(), 5);
+        assert_eq!(remainder.target_asset().unwrap_fungible().amount(), 10);
+        assert_eq!(remainder.time_in_force(), incoming_order.time_in_force());
+
+        // filled orders leave nothing behind
+        let fill = existing_orders[0].fill(10).unwrap();
+        assert!(incoming_order.remainder(&[fill]).is_none());
+    }
+
+    #[test]
+    fn own_orders_are_excluded_from_matching() {
+        let (incoming_order, existing_orders) = mock_orders();
+        let account_id = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let other_account_id = AccountId::from_hex("0x8a4e193b2c5d3e61").unwrap();
+        let incoming_order = incoming_order.with_creator(account_id);
+
+        // the account owns the perfect match and the partially fillable order, both would fill
+        let existing_orders: Vec<Order> = existing_orders
+            .into_iter()
+            .enumerate()
+            .map(|(index, order)| match index {
+                0 | 6 => order.with_creator(account_id),
+                _ => order.with_creator(other_account_id),
+            })
+            .collect();
+        let others: Vec<Order> = existing_orders
+            .iter()
+            .copied()
+            .filter(|order| order.creator() == Some(other_account_id))
+            .collect();
+
+        let (orders, cancelled) = prevent_self_trade(
+            incoming_order,
+            existing_orders.clone(),
+            SelfTradePrevention::Skip,
+        )
+        .unwrap();
+        assert_eq!(orders, others);
+        assert!(cancelled.is_empty());
+        assert!(orders
+            .iter()
+            .all(|order| order.creator() != incoming_order.creator()));
+
+        // only SWAPp notes can be consumed back by their creator
+        let (orders, cancelled) = prevent_self_trade(
+            incoming_order,
+            existing_orders.clone(),
+            SelfTradePrevention::CancelResting,
+        )
+        .unwrap();
+        assert_eq!(orders, others);
+        assert_eq!(cancelled, vec![existing_orders[6]]);
+
+        assert_eq!(
+            prevent_self_trade(
+                incoming_order,
+                existing_orders.clone(),
+                SelfTradePrevention::Abort
+            ),
+            Err(OrderError::SelfTrade)
+        );
+
+        // own orders that would not be filled do not abort the order
+        let mut not_crossing = others.clone();
+        not_crossing[1] = not_crossing[1].with_creator(account_id);
+        let (orders, cancelled) =
+            prevent_self_trade(incoming_order, not_crossing, SelfTradePrevention::Abort).unwrap();
+        assert_eq!(orders.len(), others.len() - 1);
+        assert!(cancelled.is_empty());
+    }
+
+    #[test]
+    fn quotes_measure_price_impact() {
+        let (incoming_order, existing_orders) = mock_orders();
+
+        // the best order asks 1 per 20 units received, the partially fillable order 1 per 2
+        let fills = vec![
+            existing_orders[4].fill(10).unwrap(),
+            existing_orders[6].fill(10).unwrap(),
+        ];
+        let quote = Quote::new(30, &existing_orders, fills);
+
+        assert_eq!(quote.best_price(), Some(Price::new(1, 20)));
+        assert_eq!(
+            (quote.spent(), quote.received(), quote.leftover()),
+            (20, 220, 10)
+        );
+        assert_eq!(quote.average_price(), Some(Price::new(1, 11)));
+        assert_eq!(quote.worst_price(), Some(Price::new(1, 2)));
+        assert_eq!(quote.price_impact_bps(), Some(8181));
+
+        let empty = Quote::new(30, &[incoming_order], Vec::new());
+        assert_eq!((empty.leftover(), empty.average_price()), (30, None));
+        assert_eq!(empty.price_impact_bps(), None);
+    }
+
+    #[test]
+    fn expired_orders_are_dropped() {
+        let (_, existing_orders) = mock_orders();
+        let expiring = existing_orders[0].with_expiration(Some(100));
+        let orders = vec![expiring, existing_orders[4]];
+
+        assert!(!expiring.is_expired(100));
+        assert!(expiring.is_expired(101));
+        assert_eq!(active_orders(orders.clone(), 100), orders);
+        assert_eq!(active_orders(orders, 101), vec![existing_orders[4]]);
+    }
+
+    #[test]
+    fn amended_orders_keep_unchanged_terms() {
+        let (_, existing_orders) = mock_orders();
+        let order = existing_orders[0].with_expiration(Some(100));
+
+        let resized = order.amend(Some(40), None).unwrap();
+        assert_eq!(resized.id(), None);
+        assert_eq!(resized.price(), order.price());
+        assert_eq!(resized.source_asset().unwrap_fungible().amount(), 40);
+        assert_eq!(resized.expiration(), Some(100));
+
+        let repriced = order.amend(None, Some(Price::new(5, 4))).unwrap();
+        assert_eq!(repriced.source_asset(), order.source_asset());
+        assert!(repriced.price() >= Price::new(5, 4));
+
+        assert_eq!(
+            order.amend(Some(0), None),
+            Err(OrderError::InvalidFillAmount)
+        );
+    }
+
+    #[test]
+    fn batch_auction_clears_at_a_uniform_price() {
+        let base_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let quote_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let order = |offered_faucet, offered, requested_faucet, requested| {
+            Order::new_partial(
+                None,
+                Asset::Fungible(FungibleAsset::new(offered_faucet, offered).unwrap()),
+                Asset::Fungible(FungibleAsset::new(requested_faucet, requested).unwrap()),
+            )
+        };
+
+        // asks at 1 and 2 quote per base, bids up to 1.5 and 2 quote per base
+        let asks = [
+            order(base_faucet_id, 100, quote_faucet_id, 100),
+            order(base_faucet_id, 100, quote_faucet_id, 200),
+        ];
+        let bids = [
+            order(quote_faucet_id, 150, base_faucet_id, 100),
+            order(quote_faucet_id, 100, base_faucet_id, 50),
+        ];
+        let orders = asks.iter().chain(bids.iter()).copied().collect();
+
+        let auction = clear_batch(base_faucet_id, quote_faucet_id, orders).unwrap();
+        assert_eq!(auction.clearing_price(), Price::new(3, 2));
+        assert_eq!(auction.volume(), 100);
+        assert_eq!(auction.surplus(), 0);
+
+        let amounts: Vec<(Order, u64, u64)> = auction
+            .fills()
+            .iter()
+            .map(|fill| (fill.order(), fill.source_amount(), fill.target_amount()))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![(asks[0], 100, 150), (bids[1], 99, 66), (bids[0], 51, 34)]
+        );
+
+        // every order gets at least its limit price
+        for fill in auction.fills() {
+            let price = Price::new(fill.target_amount(), fill.source_amount());
+            assert!(price >= fill.order().price());
+        }
+
+        // books that do not cross do not clear
+        assert_eq!(
+            clear_batch(base_faucet_id, quote_faucet_id, vec![asks[1], bids[0]]),
+            None
+        );
+    }
+
+    #[test]
+    fn batch_auction_settles_at_the_clearing_price() {
+        let base_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let quote_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let owner = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let asset =
+            |faucet_id, amount| Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap());
+        let order = |index: u8, offered_faucet, offered, requested_faucet, requested| {
+            Order::new_partial(
+                Some(NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap()),
+                asset(offered_faucet, offered),
+                asset(requested_faucet, requested),
+            )
+            .with_creator(owner)
+        };
+
+        // same book as the uniform price auction, cleared at 1.5 quote per base
+        let ask = order(1, base_faucet_id, 100, quote_faucet_id, 100);
+        let bids = [
+            order(2, quote_faucet_id, 150, base_faucet_id, 100),
+            order(3, quote_faucet_id, 100, base_faucet_id, 50),
+        ];
+        let auction =
+            clear_batch(base_faucet_id, quote_faucet_id, vec![ask, bids[0], bids[1]]).unwrap();
+        let settlement = auction.settlement().unwrap();
+
+        // each note is paid at its own price, then its owner is refunded to the clearing price
+        let amounts: Vec<(u64, u64)> = settlement
+            .fills()
+            .iter()
+            .map(|fill| (fill.target_amount(), fill.source_amount()))
+            .collect();
+        assert_eq!(amounts, vec![(100, 100), (50, 100), (34, 51)]);
+        assert_eq!(
+            settlement.refunds(),
+            &[
+                (owner, vec![asset(quote_faucet_id, 50)]),
+                (
+                    owner,
+                    vec![asset(quote_faucet_id, 1), asset(base_faucet_id, 16)]
+                ),
+            ]
+        );
+
+        // owners give and receive what the auction clears
+        for (batch_fill, (fill, refund)) in auction
+            .fills()
+            .iter()
+            .map(|batch_fill| (batch_fill, batch_fill.settle().unwrap()))
+        {
+            let refunded = |faucet_id| {
+                refund
+                    .iter()
+                    .filter(|asset| asset.faucet_id() == faucet_id)
+                    .map(asset_amount)
+                    .sum::<u64>()
+            };
+            let order = batch_fill.order();
+            assert_eq!(
+                fill.source_amount() - refunded(order.source_asset().faucet_id()),
+                batch_fill.source_amount()
+            );
+            assert_eq!(
+                fill.target_amount() + refunded(order.target_asset().faucet_id()),
+                batch_fill.target_amount()
+            );
+        }
+
+        // the settling account fronts the payment of the ask until the bids pay it back
+        assert_eq!(settlement.required(), vec![(quote_faucet_id, 100)]);
+
+        // refunds go to the order creator, which must be known
+        let anonymous = Order::new_partial(None, bids[1].source_asset(), bids[1].target_asset());
+        let auction = clear_batch(base_faucet_id, quote_faucet_id, vec![ask, anonymous]).unwrap();
+        assert_eq!(auction.settlement(), Err(OrderError::MissingCreator));
+    }
+
+    #[test]
+    fn crossing_orders_leave_a_spread() {
+        let faucet_a = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let faucet_b = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let order = |offered_faucet, offered, requested_faucet, requested| {
+            Order::new_partial(
+                None,
+                Asset::Fungible(FungibleAsset::new(offered_faucet, offered).unwrap()),
+                Asset::Fungible(FungibleAsset::new(requested_faucet, requested).unwrap()),
+            )
+        };
+
+        // one maker sells 100 B for 40 A, the other sells 50 A for 100 B
+        let first = order(faucet_b, 100, faucet_a, 40);
+        let second = order(faucet_a, 50, faucet_b, 100);
+        let not_crossing = order(faucet_a, 10, faucet_b, 30);
+
+        let crosses = cross_orders(vec![first], vec![not_crossing, second]);
+        assert_eq!(crosses.len(), 1);
+
+        let [first_fill, second_fill] = crosses[0].fills();
+        assert_eq!(first_fill.order(), first);
+        assert_eq!(
+            (first_fill.target_amount(), first_fill.source_amount()),
+            (40, 100)
+        );
+        assert_eq!(second_fill.order(), second);
+        assert_eq!(
+            (second_fill.target_amount(), second_fill.source_amount()),
+            (80, 40)
+        );
+        assert_eq!(crosses[0].spread(), [(faucet_a, 0), (faucet_b, 20)]);
+
+        assert!(cross_orders(vec![first], vec![not_crossing]).is_empty());
+    }
+
+    fn mock_book() -> (AccountId, AccountId, Vec<Order>) {
+        let source_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let target_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+
+        // a single resting order offering 20 target assets for 10 source assets
+        let offered = Asset::Fungible(FungibleAsset::new(target_faucet_id, 20).unwrap());
+        let requested = Asset::Fungible(FungibleAsset::new(source_faucet_id, 10).unwrap());
+        let book = vec![Order::new_partial(None, offered, requested)];
+
+        (source_faucet_id, target_faucet_id, book)
+    }
+
+    fn incoming_order(
+        source_faucet_id: AccountId,
+        source_amount: u64,
+        target_faucet_id: AccountId,
+        target_amount: u64,
+        time_in_force: TimeInForce,
+    ) -> Order {
+        let source_asset =
+            Asset::Fungible(FungibleAsset::new(source_faucet_id, source_amount).unwrap());
+        let target_asset =
+            Asset::Fungible(FungibleAsset::new(target_faucet_id, target_amount).unwrap());
+        Order::new(None, source_asset, target_asset).with_time_in_force(time_in_force)
+    }
+
+    #[test]
+    fn time_in_force_controls_partial_fills() {
+        let (source_faucet_id, target_faucet_id, book) = mock_book();
+        let budget = Duration::from_millis(100);
+
+        // the book can only fill half of the order
+        for (time_in_force, filled) in [
+            (TimeInForce::GoodTilCancelled, true),
+            (TimeInForce::ImmediateOrCancel, true),
+            (TimeInForce::FillOrKill, false),
+        ] {
+            let order = incoming_order(source_faucet_id, 20, target_faucet_id, 40, time_in_force);
+            let result = fill_order(order, book.clone(), budget);
+            match result {
+                Ok(fills) => {
+                    assert!(filled, "{:?} should not fill", time_in_force);
+                    assert_eq!(fills.len(), 1);
+                    assert_eq!(fills[0].source_amount(), 20);
+                    assert!(order.remainder(&fills).is_some());
+                }
+                Err(err) => {
+                    assert!(!filled, "{:?} should fill", time_in_force);
+                    assert_eq!(err, OrderError::FailedFill(order));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn post_only_orders_never_cross() {
+        let (source_faucet_id, target_faucet_id, book) = mock_book();
+        let budget = Duration::from_millis(100);
+
+        let crossing = incoming_order(
+            source_faucet_id,
+            10,
+            target_faucet_id,
+            20,
+            TimeInForce::PostOnly,
+        );
+        assert_eq!(
+            fill_order(crossing, book.clone(), budget),
+            Err(OrderError::WouldCross)
+        );
+
+        let resting = incoming_order(
+            source_faucet_id,
+            10,
+            target_faucet_id,
+            30,
+            TimeInForce::PostOnly,
+        );
+        assert_eq!(
+            fill_order(resting, book, budget),
+            Err(OrderError::FailedFill(resting))
+        );
+    }
+
+    #[test]
+    fn nft_listings_fill_at_their_fixed_price() {
+        let faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let nft_faucet_id = AccountId::from_hex("0x3d5a2f7c1b9e4c80").unwrap();
+        let nft = |data: u8| {
+            let details = NonFungibleAssetDetails::new(nft_faucet_id, vec![data]).unwrap();
+            Asset::NonFungible(NonFungibleAsset::new(&details).unwrap())
+        };
+        let tokens = |amount| Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap());
+        let note_id = |index: u8| NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap();
+
+        // two NFTs of the same collection listed at 100 and 80 tokens
+        let listing = Order::new(Some(note_id(1)), nft(1), tokens(100));
+        let other_listing = Order::new(Some(note_id(2)), nft(2), tokens(80));
+        assert!(!listing.is_fungible());
+        assert_eq!(listing.price(), Price::new(100, 1));
+
+        // a bid for the first NFT only matches its listing, at or above the listed price
+        let bid = Order::new(None, tokens(120), nft(1));
+        assert_eq!(match_orders(bid, listing), Ok(listing));
+        assert_eq!(
+            match_orders(bid, other_listing),
+            Err(OrderError::AssetsNotMatching)
+        );
+        assert_eq!(
+            match_orders(Order::new(None, tokens(90), nft(1)), listing),
+            Err(OrderError::PriceNotMatching)
+        );
+
+        // the listing is filled as a whole at its price, leaving nothing to post
+        let fills = fill_order(
+            bid,
+            vec![other_listing, listing],
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(
+            (fills[0].source_amount(), fills[0].target_amount()),
+            (1, 100)
+        );
+        assert_eq!(listing.fill(50), Err(OrderError::TooManyTargetAssets));
+        assert_eq!(bid.remainder(&fills), None);
+    }
+
+    #[test]
+    fn limit_price_is_checked_after_rounding() {
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let note_id = |index: u8| NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap();
+
+        // pays 9 B for at least 10 A, at most 0.9 B per A
+        let incoming_order = Order::new(
+            None,
+            asset("0x2540b08edc3b087d", 9),
+            asset("0x227bd163275aa1bf", 10),
+        );
+        let whole = Order::new(
+            Some(note_id(1)),
+            asset("0x227bd163275aa1bf", 9),
+            asset("0x2540b08edc3b087d", 7),
+        );
+        let partial = Order::new_partial(
+            Some(note_id(2)),
+            asset("0x227bd163275aa1bf", 5),
+            asset("0x2540b08edc3b087d", 4),
+        );
+
+        // the partial order matches at 0.8 B per A, but paying the 2 B left only receives 2 A
+        assert_eq!(match_orders(incoming_order, partial), Ok(partial));
+        let fill = partial.fill(2).unwrap();
+        assert_eq!(fill.source_amount(), 2);
+        assert!(fill.price() > incoming_order.price().inverse());
+
+        let fills =
+            fill_order(incoming_order, vec![whole, partial], Duration::from_secs(1)).unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order(), whole);
+    }
+
+    #[test]
+    fn rounding_rejected_partial_fill_is_replaced_by_another_order() {
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let note_id = |index: u8| NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap();
+
+        // pays 10 B for all of 11 A, at most 10/11 B per A
+        let incoming_order = Order::new(
+            None,
+            asset("0x2540b08edc3b087d", 10),
+            asset("0x227bd163275aa1bf", 11),
+        )
+        .with_time_in_force(TimeInForce::FillOrKill);
+        let cheapest = Order::new(
+            Some(note_id(1)),
+            asset("0x227bd163275aa1bf", 9),
+            asset("0x2540b08edc3b087d", 7),
+        );
+        let partial = Order::new_partial(
+            Some(note_id(2)),
+            asset("0x227bd163275aa1bf", 6),
+            asset("0x2540b08edc3b087d", 5),
+        );
+        let replacement = Order::new(
+            Some(note_id(3)),
+            asset("0x227bd163275aa1bf", 11),
+            asset("0x2540b08edc3b087d", 10),
+        );
+
+        // the 3 B left by the cheapest order only receive 3 A from the partial order, which would
+        // look like 12 A but pay 1 B per A
+        assert!(partial.fill(3).unwrap().price() > incoming_order.price().inverse());
+
+        let fills = fill_order(
+            incoming_order,
+            vec![cheapest, partial, replacement],
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order(), replacement);
+        assert_eq!(
+            (fills[0].source_amount(), fills[0].target_amount()),
+            (11, 10)
+        );
+    }
 }
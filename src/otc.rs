@@ -0,0 +1,122 @@
+use miden_client::accounts::AccountId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::OTC_OFFERS_FILE_PATH,
+    order::Order,
+    utils::{load_toml, save_toml},
+};
+
+// Declined offer
+// ================================================================================================
+
+/// OTC offer declined by the account it is directed to. The note cannot be removed by its
+/// counterparty, it stays in the store until its creator cancels it or it expires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeclinedOffer {
+    /// Note id of the offer, as hex.
+    pub note_id: String,
+    pub account_id: AccountId,
+    /// Sync height of the client when the offer was declined.
+    pub block_num: u32,
+}
+
+// OTC book
+// ================================================================================================
+
+/// Declined OTC offers, persisted in the local OTC offers file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OtcBook {
+    pub declined: Vec<DeclinedOffer>,
+}
+
+impl OtcBook {
+    pub fn load() -> Result<Self, String> {
+        load_toml(OTC_OFFERS_FILE_PATH)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_toml(OTC_OFFERS_FILE_PATH, self)
+    }
+
+    pub fn decline(&mut self, note_id: String, account_id: AccountId, block_num: u32) {
+        if !self.is_declined(&note_id, account_id) {
+            self.declined.push(DeclinedOffer {
+                note_id,
+                account_id,
+                block_num,
+            });
+        }
+    }
+
+    pub fn is_declined(&self, note_id: &str, account_id: AccountId) -> bool {
+        self.declined
+            .iter()
+            .any(|offer| offer.note_id == note_id && offer.account_id == account_id)
+    }
+
+    /// Offers directed to `account_id` among `offers` that it has not declined.
+    pub fn pending(&self, account_id: AccountId, offers: Vec<Order>) -> Vec<Order> {
+        offers
+            .into_iter()
+            .filter(|offer| offer.counterparty() == Some(account_id))
+            .filter(|offer| {
+                offer
+                    .id()
+                    .map_or(true, |id| !self.is_declined(&id.to_hex(), account_id))
+            })
+            .collect()
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+        notes::NoteId,
+    };
+
+    use super::OtcBook;
+    use crate::order::Order;
+
+    #[test]
+    fn declined_offers_are_no_longer_pending() {
+        let account_id = AccountId::from_hex("0x8a4e193b2c5d3e61").unwrap();
+        let other_account_id = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let offer = |id: u8, counterparty| {
+            let asset = |faucet_id, amount| {
+                let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+                Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+            };
+            Order::new_partial(
+                Some(NoteId::try_from_hex(&format!("0x{:064x}", id)).unwrap()),
+                asset("0x227bd163275aa1bf", 10),
+                asset("0x2540b08edc3b087d", 20),
+            )
+            .with_counterparty(Some(counterparty))
+        };
+        let offers = vec![
+            offer(1, account_id),
+            offer(2, account_id),
+            offer(3, other_account_id),
+        ];
+        assert!(offers[0].can_be_filled_by(account_id));
+        assert!(!offers[2].can_be_filled_by(account_id));
+
+        let mut book = OtcBook::default();
+        book.decline(format!("0x{:064x}", 1), account_id, 10);
+        book.decline(format!("0x{:064x}", 1), account_id, 11);
+        assert_eq!(book.declined.len(), 1);
+
+        // only the offer still addressed to the account and not declined is pending
+        assert_eq!(book.pending(account_id, offers.clone()), vec![offers[1]]);
+        assert_eq!(
+            book.pending(other_account_id, offers.clone()),
+            vec![offers[2]]
+        );
+    }
+}
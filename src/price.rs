@@ -0,0 +1,399 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of decimals used when displaying a price.
+const MAX_DISPLAY_DECIMALS: usize = 18;
+
+/// Default number of decimals used when displaying a price.
+const DEFAULT_DISPLAY_DECIMALS: usize = 8;
+
+/// Number of basis points in one.
+const BPS_SCALE: u64 = 10_000;
+
+/// Exact price expressed as a fraction of two asset amounts.
+///
+/// Prices are kept in lowest terms so that equal prices have the same representation, and are
+/// compared by cross multiplication in 128 bits so no precision is lost. A zero denominator
+/// represents an unbounded price, which is greater than any other price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Price {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Price {
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        if denominator == 0 {
+            return Price {
+                numerator: 1,
+                denominator: 0,
+            };
+        }
+
+        let divisor = gcd(numerator, denominator);
+        Price {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.denominator == 0
+    }
+
+    /// Price of the same trade quoted from the other side.
+    pub fn inverse(&self) -> Price {
+        Price::new(self.denominator, self.numerator)
+    }
+
+    /// Amount priced at this price for `amount` units, rounded up. Returns `None` for unbounded
+    /// prices or when the result does not fit in 64 bits.
+    pub fn mul_ceil(&self, amount: u64) -> Option<u64> {
+        if self.is_unbounded() {
+            return None;
+        }
+        let value = (amount as u128 * self.numerator as u128).div_ceil(self.denominator as u128);
+        u64::try_from(value).ok()
+    }
+
+    /// Amount priced at this price for `amount` units, rounded down.
+    pub fn mul_floor(&self, amount: u64) -> Option<u64> {
+        if self.is_unbounded() {
+            return None;
+        }
+        let value = amount as u128 * self.numerator as u128 / self.denominator as u128;
+        u64::try_from(value).ok()
+    }
+
+    /// Number of units `amount` buys at this price, rounded down. Returns `None` for a zero price.
+    pub fn div_floor(&self, amount: u64) -> Option<u64> {
+        self.inverse().mul_floor(amount)
+    }
+
+    /// Whether the price is more than `max_slippage_bps` basis points above `reference`.
+    pub fn exceeds_slippage(&self, reference: &Price, max_slippage_bps: u32) -> bool {
+        if reference.is_unbounded() {
+            return false;
+        }
+        if self.is_unbounded() {
+            return true;
+        }
+
+        // self * BPS_SCALE > reference * (BPS_SCALE + max_slippage_bps), compared in 256 bits
+        let lhs = mul_wide(
+            self.numerator as u128 * reference.denominator as u128,
+            BPS_SCALE,
+        );
+        let rhs = mul_wide(
+            reference.numerator as u128 * self.denominator as u128,
+            BPS_SCALE + max_slippage_bps as u64,
+        );
+        lhs > rhs
+    }
+
+    /// Signed difference with `reference` in basis points of `reference`, rounded toward zero.
+    /// Returns `None` for unbounded or zero references, or when the difference does not fit.
+    pub fn bps_from(&self, reference: &Price) -> Option<i64> {
+        if self.is_unbounded() || reference.is_unbounded() || reference.numerator == 0 {
+            return None;
+        }
+
+        let value = self.numerator as u128 * reference.denominator as u128;
+        let reference_value = reference.numerator as u128 * self.denominator as u128;
+        let difference = value.abs_diff(reference_value);
+        let bps =
+            i64::try_from(difference.checked_mul(BPS_SCALE as u128)? / reference_value).ok()?;
+
+        Some(if value < reference_value { -bps } else { bps })
+    }
+
+    /// Sum of both prices. Returns `None` for unbounded prices or when the result does not fit.
+    pub fn checked_add(&self, other: &Price) -> Option<Price> {
+        if self.is_unbounded() || other.is_unbounded() {
+            return None;
+        }
+        let numerator = (self.numerator as u128 * other.denominator as u128)
+            .checked_add(other.numerator as u128 * self.denominator as u128)?;
+        from_wide(
+            numerator,
+            self.denominator as u128 * other.denominator as u128,
+        )
+    }
+
+    /// Difference with a lower price. Returns `None` if `other` is higher, for unbounded prices,
+    /// or when the result does not fit.
+    pub fn checked_sub(&self, other: &Price) -> Option<Price> {
+        if self.is_unbounded() || other.is_unbounded() {
+            return None;
+        }
+        let numerator = (self.numerator as u128 * other.denominator as u128)
+            .checked_sub(other.numerator as u128 * self.denominator as u128)?;
+        from_wide(
+            numerator,
+            self.denominator as u128 * other.denominator as u128,
+        )
+    }
+
+    /// Price halfway between both prices.
+    pub fn midpoint(&self, other: &Price) -> Option<Price> {
+        let sum = self.checked_add(other)?;
+        from_wide(sum.numerator as u128, sum.denominator as u128 * 2)
+    }
+
+    /// Highest multiple of `tick` not above the price. Returns `None` for a zero or unbounded
+    /// tick, or when the result does not fit.
+    pub fn floor_to(&self, tick: &Price) -> Option<Price> {
+        let (value, tick_value) = self.ticks(tick)?;
+        self.tick_multiple(tick, value / tick_value)
+    }
+
+    /// Lowest multiple of `tick` not below the price.
+    pub fn ceil_to(&self, tick: &Price) -> Option<Price> {
+        let (value, tick_value) = self.ticks(tick)?;
+        self.tick_multiple(tick, value.div_ceil(tick_value))
+    }
+
+    /// The price and `tick` over their common denominator.
+    fn ticks(&self, tick: &Price) -> Option<(u128, u128)> {
+        if self.is_unbounded() || tick.is_unbounded() || tick.numerator == 0 {
+            return None;
+        }
+        Some((
+            self.numerator as u128 * tick.denominator as u128,
+            tick.numerator as u128 * self.denominator as u128,
+        ))
+    }
+
+    fn tick_multiple(&self, tick: &Price, ticks: u128) -> Option<Price> {
+        from_wide(
+            ticks.checked_mul(tick.numerator as u128)?,
+            tick.denominator as u128,
+        )
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lhs = self.numerator as u128 * other.denominator as u128;
+        let rhs = other.numerator as u128 * self.denominator as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Price {
+    /// Formats the price as a decimal number rounded to the formatter precision (8 by default).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_unbounded() {
+            return f.pad_integral(true, "", "inf");
+        }
+
+        let decimals = f
+            .precision()
+            .unwrap_or(DEFAULT_DISPLAY_DECIMALS)
+            .min(MAX_DISPLAY_DECIMALS);
+        let scale = 10u128.pow(decimals as u32);
+        let denominator = self.denominator as u128;
+        let scaled = (self.numerator as u128 * scale + denominator / 2) / denominator;
+
+        let digits = if decimals == 0 {
+            scaled.to_string()
+        } else {
+            format!(
+                "{}.{:0width$}",
+                scaled / scale,
+                scaled % scale,
+                width = decimals
+            )
+        };
+
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl FromStr for Price {
+    type Err = String;
+
+    /// Parses a decimal number such as `1.25`, or a fraction such as `5/4`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid price: {}", value);
+
+        if let Some((numerator, denominator)) = value.split_once('/') {
+            let numerator = numerator.trim().parse().map_err(|_| invalid())?;
+            let denominator: u64 = denominator.trim().parse().map_err(|_| invalid())?;
+            if denominator == 0 {
+                return Err(invalid());
+            }
+            return Ok(Price::new(numerator, denominator));
+        }
+
+        let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+        if fraction.len() > MAX_DISPLAY_DECIMALS
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+            || (integer.is_empty() && fraction.is_empty())
+        {
+            return Err(invalid());
+        }
+
+        let scale = 10u128.pow(fraction.len() as u32);
+        let integer: u128 = if integer.is_empty() {
+            0
+        } else {
+            integer.parse().map_err(|_| invalid())?
+        };
+        let fraction: u128 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse().map_err(|_| invalid())?
+        };
+
+        // reduce before narrowing to 64 bits so prices such as 0.000000000000000001 still fit
+        let numerator = integer
+            .checked_mul(scale)
+            .and_then(|scaled| scaled.checked_add(fraction))
+            .ok_or_else(invalid)?;
+        let divisor = gcd_wide(numerator, scale);
+        let numerator = u64::try_from(numerator / divisor).map_err(|_| invalid())?;
+        let denominator = u64::try_from(scale / divisor).map_err(|_| invalid())?;
+        Ok(Price::new(numerator, denominator))
+    }
+}
+
+/// Multiplies a 128 bit value by a 64 bit value, returning the (high, low) halves of the result.
+fn mul_wide(value: u128, multiplier: u64) -> (u128, u128) {
+    let low = (value as u64 as u128) * multiplier as u128;
+    let high = (value >> 64) * multiplier as u128;
+
+    let (low, carry) = low.overflowing_add(high << 64);
+    ((high >> 64) + carry as u128, low)
+}
+
+/// Reduces a 128 bit fraction to a price, if it fits in 64 bits once in lowest terms.
+fn from_wide(numerator: u128, denominator: u128) -> Option<Price> {
+    let divisor = gcd_wide(numerator, denominator).max(1);
+    Some(Price::new(
+        u64::try_from(numerator / divisor).ok()?,
+        u64::try_from(denominator / divisor).ok()?,
+    ))
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn gcd_wide(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::Price;
+
+    #[test]
+    fn prices_are_normalized_and_compared_exactly() {
+        assert_eq!(Price::new(2, 4), Price::new(1, 2));
+        assert_eq!(Price::new(0, 5), Price::new(0, 1));
+        assert_eq!(Price::new(7, 0), Price::new(1, 0));
+
+        // close prices on 10-decimal amounts that collapse to the same f64
+        let n = 1u64 << 60;
+        let a = Price::new(n, n + 1);
+        let b = Price::new(n + 1, n + 2);
+        assert_eq!(
+            a.numerator as f64 / a.denominator as f64,
+            b.numerator as f64 / b.denominator as f64
+        );
+        assert!(a < b);
+
+        assert!(Price::new(u64::MAX, 1) < Price::new(1, 0));
+        assert_eq!(Price::new(3, 2).inverse(), Price::new(2, 3));
+    }
+
+    #[test]
+    fn slippage_is_checked_exactly() {
+        let best = Price::new(100, 1);
+        assert!(!Price::new(101, 1).exceeds_slippage(&best, 100));
+        assert!(Price::new(10_101, 100).exceeds_slippage(&best, 100));
+        assert!(!Price::new(10_101, 100).exceeds_slippage(&best, 101));
+        assert!(Price::new(1, 0).exceeds_slippage(&best, u32::MAX));
+
+        // large amounts do not overflow
+        let best = Price::new(u64::MAX - 1, u64::MAX);
+        assert!(!Price::new(u64::MAX - 1, u64::MAX).exceeds_slippage(&best, 0));
+        assert!(Price::new(1, 1).exceeds_slippage(&best, 0));
+        assert!(!Price::new(1, 1).exceeds_slippage(&best, 1));
+    }
+
+    #[test]
+    fn price_differences_are_measured_in_bps() {
+        let reference = Price::new(2, 1);
+        assert_eq!(Price::new(201, 100).bps_from(&reference), Some(50));
+        assert_eq!(Price::new(199, 100).bps_from(&reference), Some(-50));
+        assert_eq!(reference.bps_from(&reference), Some(0));
+        assert_eq!(reference.bps_from(&Price::new(0, 1)), None);
+        assert_eq!(reference.bps_from(&Price::new(1, 0)), None);
+    }
+
+    #[test]
+    fn prices_are_combined_and_rounded_to_ticks() {
+        let bid = Price::new(99, 100);
+        let ask = Price::new(101, 100);
+        assert_eq!(ask.checked_sub(&bid), Some(Price::new(1, 50)));
+        assert_eq!(bid.checked_sub(&ask), None);
+        assert_eq!(bid.checked_add(&ask), Some(Price::new(2, 1)));
+        assert_eq!(bid.midpoint(&ask), Some(Price::new(1, 1)));
+        assert_eq!(bid.midpoint(&Price::new(1, 0)), None);
+
+        let tick = Price::new(1, 20);
+        assert_eq!(Price::new(123, 100).floor_to(&tick), Some(Price::new(6, 5)));
+        assert_eq!(Price::new(123, 100).ceil_to(&tick), Some(Price::new(5, 4)));
+        assert_eq!(Price::new(5, 4).ceil_to(&tick), Some(Price::new(5, 4)));
+        assert_eq!(Price::new(1, 3).floor_to(&Price::new(0, 1)), None);
+    }
+
+    #[test]
+    fn prices_are_displayed_as_rounded_decimals() {
+        assert_eq!(Price::new(2, 3).to_string(), "0.66666667");
+        assert_eq!(format!("{:.2}", Price::new(2, 3)), "0.67");
+        assert_eq!(format!("{:<8.2}|", Price::new(5, 2)), "2.50    |");
+        assert_eq!(format!("{:.0}", Price::new(5, 2)), "3");
+        assert_eq!(Price::new(1, 0).to_string(), "inf");
+    }
+
+    #[test]
+    fn prices_are_parsed_exactly() {
+        assert_eq!("1.25".parse(), Ok(Price::new(5, 4)));
+        assert_eq!("5/4".parse(), Ok(Price::new(5, 4)));
+        assert_eq!("3".parse(), Ok(Price::new(3, 1)));
+        assert_eq!(".5".parse(), Ok(Price::new(1, 2)));
+        assert_eq!(
+            "0.000000000000000001".parse(),
+            Ok(Price::new(1, 10u64.pow(18)))
+        );
+        assert!("1/0".parse::<Price>().is_err());
+        assert!("1.2.3".parse::<Price>().is_err());
+        assert!("-1".parse::<Price>().is_err());
+        assert!(".".parse::<Price>().is_err());
+
+        assert_eq!(Price::new(5, 4).mul_ceil(10), Some(13));
+        assert_eq!(Price::new(1, 0).mul_ceil(10), None);
+        assert_eq!(Price::new(5, 4).mul_floor(10), Some(12));
+        assert_eq!(Price::new(5, 4).div_floor(10), Some(8));
+        assert_eq!(Price::new(0, 1).div_floor(10), None);
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use miden_client::accounts::AccountId;
+
+use crate::{
+    order::{sort_orders, Fill, Order},
+    price::Price,
+    selection::{select_greedy, Candidate},
+};
+
+/// A trade in a single market, paying the source asset for the target asset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leg {
+    source_faucet_id: AccountId,
+    target_faucet_id: AccountId,
+    fills: Vec<Fill>,
+}
+
+impl Leg {
+    pub fn source_faucet_id(&self) -> AccountId {
+        self.source_faucet_id
+    }
+
+    pub fn target_faucet_id(&self) -> AccountId {
+        self.target_faucet_id
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Amount of the source asset paid in this leg.
+    pub fn amount_in(&self) -> u64 {
+        self.fills.iter().map(Fill::target_amount).sum()
+    }
+
+    /// Amount of the target asset received in this leg.
+    pub fn amount_out(&self) -> u64 {
+        self.fills.iter().map(Fill::source_amount).sum()
+    }
+
+    /// Average amount paid per unit received.
+    pub fn price(&self) -> Price {
+        Price::new(self.amount_in(), self.amount_out())
+    }
+}
+
+/// A sequence of legs where the asset received in a leg is paid in the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    legs: Vec<Leg>,
+}
+
+impl Route {
+    pub fn legs(&self) -> &[Leg] {
+        &self.legs
+    }
+
+    /// Fills of every leg, in execution order.
+    pub fn fills(&self) -> impl Iterator<Item = &Fill> {
+        self.legs.iter().flat_map(|leg| leg.fills.iter())
+    }
+
+    pub fn amount_in(&self) -> u64 {
+        self.legs.first().map_or(0, Leg::amount_in)
+    }
+
+    pub fn amount_out(&self) -> u64 {
+        self.legs.last().map_or(0, Leg::amount_out)
+    }
+
+    /// Amount of the first source asset paid per unit of the last target asset received.
+    pub fn price(&self) -> Price {
+        Price::new(self.amount_in(), self.amount_out())
+    }
+}
+
+// Routing
+// ================================================================================================
+
+/// Finds the route receiving the most of `target_faucet_id` for `amount` of `source_faucet_id`,
+/// across at most `max_hops` markets.
+///
+/// Markets are built from the existing orders, an order offering asset B for asset A allowing a
+/// hop from A to B. Every simple path is evaluated by walking each market from the best price,
+/// the amount received in a leg being spent in the next one.
+pub fn find_route(
+    source_faucet_id: AccountId,
+    target_faucet_id: AccountId,
+    amount: u64,
+    max_hops: usize,
+    existing_orders: Vec<Order>,
+) -> Option<Route> {
+    if source_faucet_id == target_faucet_id {
+        return None;
+    }
+
+    let mut markets: BTreeMap<(u64, u64), Vec<Order>> = BTreeMap::new();
+    for order in existing_orders {
+        let pay = order.target_asset().faucet_id();
+        let receive = order.source_asset().faucet_id();
+        if pay != receive {
+            markets
+                .entry((u64::from(pay), u64::from(receive)))
+                .or_default()
+                .push(order);
+        }
+    }
+    let markets: BTreeMap<(u64, u64), Vec<Order>> = markets
+        .into_iter()
+        .map(|(pair, orders)| (pair, sort_orders(orders)))
+        .collect();
+
+    let mut search = RouteSearch {
+        markets: &markets,
+        target_faucet_id,
+        max_hops,
+        visited: BTreeSet::from([u64::from(source_faucet_id)]),
+        legs: Vec::new(),
+        best: None,
+    };
+    search.explore(source_faucet_id, amount);
+    search.best
+}
+
+struct RouteSearch<'a> {
+    /// Orders of each market keyed by (paid faucet, received faucet), sorted by price.
+    markets: &'a BTreeMap<(u64, u64), Vec<Order>>,
+    target_faucet_id: AccountId,
+    max_hops: usize,
+    visited: BTreeSet<u64>,
+    legs: Vec<Leg>,
+    best: Option<Route>,
+}
+
+impl RouteSearch<'_> {
+    fn explore(&mut self, faucet_id: AccountId, amount: u64) {
+        if faucet_id == self.target_faucet_id {
+            let is_better = self.best.as_ref().map_or(true, |best| {
+                amount > best.amount_out()
+                    || (amount == best.amount_out() && self.legs.len() < best.legs.len())
+            });
+            if is_better {
+                self.best = Some(Route {
+                    legs: self.legs.clone(),
+                });
+            }
+            return;
+        }
+
+        if self.legs.len() == self.max_hops || amount == 0 {
+            return;
+        }
+
+        let markets = self.markets;
+        for ((pay, receive), orders) in markets.range((u64::from(faucet_id), 0)..) {
+            if *pay != u64::from(faucet_id) {
+                break;
+            }
+            if self.visited.contains(receive) {
+                continue;
+            }
+
+            let Some(leg) = walk_market(faucet_id, orders, amount) else {
+                continue;
+            };
+            let next_faucet_id = leg.target_faucet_id;
+            let amount_out = leg.amount_out();
+
+            self.visited.insert(*receive);
+            self.legs.push(leg);
+            self.explore(next_faucet_id, amount_out);
+            self.legs.pop();
+            self.visited.remove(receive);
+        }
+    }
+}
+
+/// Spends up to `amount` in a market, from the best price. Orders that can only be consumed as a
+/// whole are skipped when they do not fit.
+fn walk_market(source_faucet_id: AccountId, orders: &[Order], amount: u64) -> Option<Leg> {
+    let candidates: Vec<Candidate> = orders.iter().copied().map(Candidate::from).collect();
+    let selection = select_greedy(&candidates, amount);
+
+    let fills = selection
+        .fills
+        .into_iter()
+        .map(|(index, cost)| orders[index].fill(cost))
+        .collect::<Result<Vec<Fill>, _>>()
+        .ok()
+        .filter(|fills| !fills.is_empty())?;
+
+    Some(Leg {
+        source_faucet_id,
+        target_faucet_id: orders[0].source_asset().faucet_id(),
+        fills,
+    })
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+    };
+
+    use super::find_route;
+    use crate::order::Order;
+
+    fn faucets() -> [AccountId; 3] {
+        [
+            "0x227bd163275aa1bf",
+            "0x2540b08edc3b087d",
+            "0x29b86f9443ad907a",
+        ]
+        .map(|hex| AccountId::from_hex(hex).unwrap())
+    }
+
+    /// A resting order offering `offered` of `offered_faucet` for `requested` of
+    /// `requested_faucet`.
+    fn order(
+        offered_faucet: AccountId,
+        offered: u64,
+        requested_faucet: AccountId,
+        requested: u64,
+    ) -> Order {
+        let offered = Asset::Fungible(FungibleAsset::new(offered_faucet, offered).unwrap());
+        let requested = Asset::Fungible(FungibleAsset::new(requested_faucet, requested).unwrap());
+        Order::new_partial(None, offered, requested)
+    }
+
+    #[test]
+    fn route_goes_through_intermediate_market() {
+        let [a, b, c] = faucets();
+
+        // no A/C book, A -> B at 2 B per A and B -> C at 3 C per B
+        let orders = vec![order(b, 200, a, 100), order(c, 600, b, 200)];
+
+        assert_eq!(find_route(a, c, 50, 1, orders.clone()), None);
+
+        let route = find_route(a, c, 50, 2, orders).unwrap();
+        assert_eq!(route.legs().len(), 2);
+        assert_eq!(route.legs()[0].target_faucet_id(), b);
+        assert_eq!(route.legs()[0].amount_out(), 100);
+        assert_eq!(route.amount_in(), 50);
+        assert_eq!(route.amount_out(), 300);
+    }
+
+    #[test]
+    fn route_prefers_the_best_path() {
+        let [a, b, c] = faucets();
+
+        // the direct book gives 5 C per A, the two hop path 6 C per A
+        let direct = order(c, 500, a, 100);
+        let orders = vec![direct, order(b, 200, a, 100), order(c, 600, b, 200)];
+
+        let route = find_route(a, c, 10, 3, orders.clone()).unwrap();
+        assert_eq!(route.legs().len(), 2);
+        assert_eq!(route.amount_out(), 60);
+
+        let route = find_route(a, c, 10, 1, orders).unwrap();
+        assert_eq!(route.legs().len(), 1);
+        assert_eq!(route.legs()[0].fills()[0].order(), direct);
+        assert_eq!(route.amount_out(), 50);
+    }
+}
@@ -0,0 +1,291 @@
+use core::fmt;
+
+use miden_client::{assets::Asset, notes::NoteId};
+use miden_lib::notes::scripts;
+use miden_objects::{accounts::AccountVaultDelta, Digest};
+
+use crate::{
+    notes::swapp_script_root,
+    order::{asset_amount, Fill, Order},
+};
+
+// Trusted scripts
+// ================================================================================================
+
+/// Roots of the note scripts the client agrees to consume when filling orders: the SWAPp script
+/// of this crate and the standard SWAP script. Anyone can publish a note under a swap tag, and a
+/// look-alike script could take more from the consuming account than the order shows.
+pub fn trusted_swap_scripts() -> [Digest; 2] {
+    [swapp_script_root(), scripts::swap().hash()]
+}
+
+pub fn is_trusted_script(script_root: Digest) -> bool {
+    trusted_swap_scripts().contains(&script_root)
+}
+
+// Vault delta
+// ================================================================================================
+
+/// Net change of each asset in the vault of an account, non-fungible assets counting as one.
+#[derive(Debug, Clone, Default)]
+pub struct VaultDelta {
+    changes: Vec<(Asset, i128)>,
+}
+
+/// Asset whose expected and simulated changes differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VaultMismatch {
+    pub asset: Asset,
+    pub expected: i128,
+    pub actual: i128,
+}
+
+impl VaultDelta {
+    /// Change promised to the account executing `fills`, posting `remainder` and reclaiming its
+    /// orders of `reclaimed`, given the assets carried by the consumed notes in `note_assets`.
+    ///
+    /// Every asset of a filled or reclaimed note is received, less the part of the offered asset
+    /// re-emitted by a partial fill, while the assets the filled orders request and the remainder
+    /// are spent. An order whose note is not in `note_assets` is taken to carry its source asset
+    /// alone.
+    pub fn of_fills(
+        fills: &[Fill],
+        remainder: Option<Order>,
+        reclaimed: &[Order],
+        note_assets: &[(NoteId, Vec<Asset>)],
+    ) -> Self {
+        let assets_of = |order: &Order| {
+            note_assets
+                .iter()
+                .find(|(note_id, _)| order.id() == Some(*note_id))
+                .map_or_else(|| vec![order.source_asset()], |(_, assets)| assets.clone())
+        };
+
+        let mut delta = VaultDelta::default();
+        for fill in fills {
+            let order = fill.order();
+            for asset in assets_of(&order) {
+                delta.add(asset, i128::from(asset_amount(&asset)));
+            }
+            delta.add(
+                order.source_asset(),
+                -i128::from(order.source_amount() - fill.source_amount()),
+            );
+            delta.add(order.target_asset(), -i128::from(fill.target_amount()));
+        }
+        if let Some(remainder) = remainder {
+            delta.add(
+                remainder.source_asset(),
+                -i128::from(remainder.source_amount()),
+            );
+        }
+        for order in reclaimed {
+            for asset in assets_of(order) {
+                delta.add(asset, i128::from(asset_amount(&asset)));
+            }
+        }
+        delta
+    }
+
+    /// Adds `amount` to the change of `asset`, fungible assets of the same faucet sharing one
+    /// change whatever their amount.
+    pub fn add(&mut self, asset: Asset, amount: i128) {
+        match self
+            .changes
+            .iter_mut()
+            .find(|(known, _)| known.vault_key() == asset.vault_key())
+        {
+            Some((_, change)) => *change += amount,
+            None => self.changes.push((asset, amount)),
+        }
+        self.changes.retain(|(_, change)| *change != 0);
+    }
+
+    /// Changes of each asset, in the order they were first seen.
+    pub fn changes(&self) -> &[(Asset, i128)] {
+        &self.changes
+    }
+
+    /// Assets whose change differs in `actual`.
+    pub fn mismatches(&self, actual: &VaultDelta) -> Vec<VaultMismatch> {
+        let change = |delta: &VaultDelta, asset: &Asset| {
+            delta
+                .changes
+                .iter()
+                .find(|(known, _)| known.vault_key() == asset.vault_key())
+                .map_or(0, |(_, change)| *change)
+        };
+
+        let mut mismatches: Vec<VaultMismatch> = self
+            .changes
+            .iter()
+            .map(|(asset, expected)| VaultMismatch {
+                asset: *asset,
+                expected: *expected,
+                actual: change(actual, asset),
+            })
+            .filter(|mismatch| mismatch.expected != mismatch.actual)
+            .collect();
+        mismatches.extend(
+            actual
+                .changes
+                .iter()
+                .filter(|(asset, _)| change(self, asset) == 0)
+                .map(|(asset, actual)| VaultMismatch {
+                    asset: *asset,
+                    expected: 0,
+                    actual: *actual,
+                }),
+        );
+        mismatches
+    }
+}
+
+impl From<&AccountVaultDelta> for VaultDelta {
+    fn from(vault_delta: &AccountVaultDelta) -> Self {
+        let mut delta = VaultDelta::default();
+        for asset in &vault_delta.added_assets {
+            delta.add(*asset, i128::from(asset_amount(asset)));
+        }
+        for asset in &vault_delta.removed_assets {
+            delta.add(*asset, -i128::from(asset_amount(asset)));
+        }
+        delta
+    }
+}
+
+impl fmt::Display for VaultMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.asset {
+            Asset::Fungible(_) => "",
+            Asset::NonFungible(_) => " (non-fungible)",
+        };
+        write!(
+            f,
+            "faucet {}{}: expected {:+}, simulated {:+}",
+            self.asset.faucet_id(),
+            kind,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+        notes::NoteId,
+    };
+
+    use super::{VaultDelta, VaultMismatch};
+    use crate::order::Order;
+
+    #[test]
+    fn simulated_vault_changes_must_match_the_fills() {
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let note_id = NoteId::try_from_hex(&format!("0x{:064x}", 1)).unwrap();
+
+        // half of an order offering 10 A for 20 B is filled, and 30 B are posted for A
+        let order = Order::new_partial(
+            Some(note_id),
+            asset("0x227bd163275aa1bf", 10),
+            asset("0x2540b08edc3b087d", 20),
+        );
+        let fill = order.fill(10).unwrap();
+        let remainder = Order::new(
+            None,
+            asset("0x2540b08edc3b087d", 30),
+            asset("0x227bd163275aa1bf", 15),
+        );
+        let expected = VaultDelta::of_fills(&[fill], Some(remainder), &[], &[]);
+        assert_eq!(
+            expected.changes(),
+            &[
+                (asset("0x227bd163275aa1bf", 10), 5),
+                (asset("0x2540b08edc3b087d", 20), -40)
+            ]
+        );
+
+        let mut honest = VaultDelta::default();
+        honest.add(asset("0x2540b08edc3b087d", 40), -40);
+        honest.add(asset("0x227bd163275aa1bf", 5), 5);
+        assert!(expected.mismatches(&honest).is_empty());
+
+        // a look-alike note taking more of B and some of another asset
+        let mut malicious = honest.clone();
+        malicious.add(asset("0x2540b08edc3b087d", 60), -60);
+        malicious.add(asset("0x2a3c5f8e1d7b4c90", 50), -50);
+        assert_eq!(
+            expected.mismatches(&malicious),
+            vec![
+                VaultMismatch {
+                    asset: asset("0x2540b08edc3b087d", 20),
+                    expected: -40,
+                    actual: -100
+                },
+                VaultMismatch {
+                    asset: asset("0x2a3c5f8e1d7b4c90", 50),
+                    expected: 0,
+                    actual: -50
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn every_asset_of_a_consumed_note_is_expected() {
+        let asset = |faucet_id, amount| {
+            let faucet_id = AccountId::from_hex(faucet_id).unwrap();
+            Asset::Fungible(FungibleAsset::new(faucet_id, amount).unwrap())
+        };
+        let note_id = |index: u8| NoteId::try_from_hex(&format!("0x{:064x}", index)).unwrap();
+
+        // a note offering 10 A for 20 B also carries 3 C, handed over when it is filled
+        let order = Order::new(
+            Some(note_id(1)),
+            asset("0x227bd163275aa1bf", 10),
+            asset("0x2540b08edc3b087d", 20),
+        );
+        let note_assets = vec![(
+            note_id(1),
+            vec![
+                asset("0x227bd163275aa1bf", 10),
+                asset("0x2a3c5f8e1d7b4c90", 3),
+            ],
+        )];
+        let fill = order.fill(20).unwrap();
+        let expected = VaultDelta::of_fills(&[fill], None, &[], &note_assets);
+        assert_eq!(
+            expected.changes(),
+            &[
+                (asset("0x227bd163275aa1bf", 10), 10),
+                (asset("0x2a3c5f8e1d7b4c90", 3), 3),
+                (asset("0x2540b08edc3b087d", 20), -20)
+            ]
+        );
+
+        let mut actual = VaultDelta::default();
+        actual.add(asset("0x227bd163275aa1bf", 10), 10);
+        actual.add(asset("0x2a3c5f8e1d7b4c90", 3), 3);
+        actual.add(asset("0x2540b08edc3b087d", 20), -20);
+        assert!(expected.mismatches(&actual).is_empty());
+
+        // reclaiming the note returns every asset it carries
+        let reclaimed = VaultDelta::of_fills(&[], None, &[order], &note_assets);
+        assert_eq!(
+            reclaimed.changes(),
+            &[
+                (asset("0x227bd163275aa1bf", 10), 10),
+                (asset("0x2a3c5f8e1d7b4c90", 3), 3)
+            ]
+        );
+    }
+}
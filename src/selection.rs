@@ -0,0 +1,346 @@
+use std::{
+    cmp::Ordering,
+    time::{Duration, Instant},
+};
+
+use crate::price::Price;
+
+/// An existing order that can be used to fill an incoming order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// Amount the incoming order pays to consume the whole order.
+    pub cost: u64,
+    /// Amount the incoming order receives when consuming the whole order.
+    pub gain: u64,
+    /// Whether the order can be partially filled.
+    pub divisible: bool,
+    /// Highest cost per unit of gain the incoming order accepts once a partial fill is rounded.
+    pub limit: Option<Price>,
+}
+
+impl Candidate {
+    /// Partial fill paying at most `budget`, as `(cost, gain)`. The gain is rounded down, so the
+    /// cost is lowered to the least amount receiving it, and fills priced past the limit are
+    /// refused.
+    fn fill_within(&self, budget: u64) -> Option<(u64, u64)> {
+        let gain = pro_rata(budget.min(self.cost), self);
+        if gain == 0 {
+            return None;
+        }
+
+        let cost = (gain as u128 * self.cost as u128).div_ceil(self.gain as u128) as u64;
+        if self
+            .limit
+            .is_some_and(|limit| Price::new(cost, gain) > limit)
+        {
+            return None;
+        }
+        Some((cost, gain))
+    }
+}
+
+/// A set of (possibly partial) fills, as `(candidate index, amount paid)` pairs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub fills: Vec<(usize, u64)>,
+    pub cost: u64,
+    pub gain: u64,
+}
+
+impl Selection {
+    fn push(&mut self, index: usize, cost: u64, gain: u64) {
+        self.fills.push((index, cost));
+        self.cost += cost;
+        self.gain += gain;
+    }
+
+    /// A selection is better if it receives more, or receives as much for less. Equal selections
+    /// are ranked by fewer fills, then by the fills of the earliest candidates, so the outcome
+    /// does not depend on the order in which they are found.
+    fn is_better_than(&self, other: &Selection) -> bool {
+        let sorted_fills = |selection: &Selection| {
+            let mut fills = selection.fills.clone();
+            fills.sort_unstable();
+            fills
+        };
+        other
+            .gain
+            .cmp(&self.gain)
+            .then(self.cost.cmp(&other.cost))
+            .then(self.fills.len().cmp(&other.fills.len()))
+            .then_with(|| sorted_fills(self).cmp(&sorted_fills(other)))
+            .is_lt()
+    }
+}
+
+/// Outcome of the greedy and optimal selection over the same candidates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionReport {
+    pub greedy: Selection,
+    pub optimal: Selection,
+    /// Whether the optimal search explored the whole space within its time budget.
+    pub complete: bool,
+}
+
+// Greedy selection
+// ================================================================================================
+
+/// Walks the candidates in the given order, consuming every order that fits in the budget and
+/// partially filling the divisible orders that do not.
+pub fn select_greedy(candidates: &[Candidate], budget: u64) -> Selection {
+    let mut selection = Selection::default();
+    let mut remaining = budget;
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        if remaining == 0 {
+            break;
+        }
+
+        if candidate.cost <= remaining {
+            selection.push(index, candidate.cost, candidate.gain);
+            remaining -= candidate.cost;
+        } else if candidate.divisible {
+            if let Some((cost, gain)) = candidate.fill_within(remaining) {
+                selection.push(index, cost, gain);
+                remaining -= cost;
+            }
+        }
+    }
+
+    selection
+}
+
+// Optimal selection
+// ================================================================================================
+
+/// Finds the selection receiving the most for at most `budget`, using a branch and bound search
+/// over the orders that can only be consumed as a whole. Divisible orders fill whatever budget is
+/// left, best ratio first.
+///
+/// The search starts from the greedy selection and stops at `time_budget`, returning the best
+/// selection found so far, so the result is never worse than [`select_greedy`].
+pub fn select_optimal(
+    candidates: &[Candidate],
+    budget: u64,
+    time_budget: Duration,
+) -> SelectionReport {
+    let greedy = select_greedy(candidates, budget);
+
+    let (mut whole, mut divisible): (Vec<usize>, Vec<usize>) =
+        (0..candidates.len()).partition(|&index| !candidates[index].divisible);
+    whole.sort_by(|&a, &b| compare_ratio(&candidates[b], &candidates[a]));
+    divisible.sort_by(|&a, &b| compare_ratio(&candidates[b], &candidates[a]));
+
+    let mut search = Search {
+        candidates,
+        whole,
+        divisible,
+        deadline: Instant::now() + time_budget,
+        best: greedy.clone(),
+        complete: true,
+    };
+    search.explore(0, budget, &mut Selection::default());
+
+    SelectionReport {
+        greedy,
+        optimal: search.best,
+        complete: search.complete,
+    }
+}
+
+struct Search<'a> {
+    candidates: &'a [Candidate],
+    /// Indices of orders consumed as a whole, best ratio first.
+    whole: Vec<usize>,
+    /// Indices of divisible orders, best ratio first.
+    divisible: Vec<usize>,
+    deadline: Instant,
+    best: Selection,
+    complete: bool,
+}
+
+impl Search<'_> {
+    fn explore(&mut self, depth: usize, remaining: u64, current: &mut Selection) {
+        if Instant::now() >= self.deadline {
+            self.complete = false;
+            return;
+        }
+
+        let completed = self.complete_with_divisible(current, remaining);
+        if completed.is_better_than(&self.best) {
+            self.best = completed;
+        }
+
+        if depth == self.whole.len()
+            || (current.gain as u128 + self.bound(depth, remaining) as u128)
+                < self.best.gain as u128
+        {
+            return;
+        }
+
+        let index = self.whole[depth];
+        let candidate = self.candidates[index];
+
+        if candidate.cost <= remaining {
+            current.push(index, candidate.cost, candidate.gain);
+            self.explore(depth + 1, remaining - candidate.cost, current);
+            current.fills.pop();
+            current.cost -= candidate.cost;
+            current.gain -= candidate.gain;
+        }
+
+        self.explore(depth + 1, remaining, current);
+    }
+
+    /// Fills the remaining budget with divisible orders.
+    fn complete_with_divisible(&self, current: &Selection, mut remaining: u64) -> Selection {
+        let mut selection = current.clone();
+        for &index in &self.divisible {
+            if remaining == 0 {
+                break;
+            }
+
+            if let Some((cost, gain)) = self.candidates[index].fill_within(remaining) {
+                selection.push(index, cost, gain);
+                remaining -= cost;
+            }
+        }
+        selection
+    }
+
+    /// Upper bound of the gain reachable from `depth` with `remaining` budget, relaxing every
+    /// order to be divisible.
+    fn bound(&self, depth: usize, mut remaining: u64) -> u64 {
+        let mut rest: Vec<usize> = self.whole[depth..]
+            .iter()
+            .chain(self.divisible.iter())
+            .copied()
+            .collect();
+        rest.sort_by(|&a, &b| compare_ratio(&self.candidates[b], &self.candidates[a]));
+
+        let mut bound = 0u64;
+        for index in rest {
+            if remaining == 0 {
+                break;
+            }
+
+            let candidate = self.candidates[index];
+            if candidate.cost <= remaining {
+                bound = bound.saturating_add(candidate.gain);
+                remaining -= candidate.cost;
+            } else {
+                let gain =
+                    (remaining as u128 * candidate.gain as u128).div_ceil(candidate.cost as u128);
+                bound = bound.saturating_add(gain as u64);
+                remaining = 0;
+            }
+        }
+        bound
+    }
+}
+
+// Helpers
+// ================================================================================================
+
+/// Amount received when paying `cost` to a divisible order, rounded down.
+fn pro_rata(cost: u64, candidate: &Candidate) -> u64 {
+    if candidate.cost == 0 {
+        return 0;
+    }
+    (cost as u128 * candidate.gain as u128 / candidate.cost as u128) as u64
+}
+
+/// Compares the gain per unit of cost of two candidates.
+fn compare_ratio(a: &Candidate, b: &Candidate) -> Ordering {
+    (a.gain as u128 * b.cost as u128).cmp(&(b.gain as u128 * a.cost as u128))
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{select_greedy, select_optimal, Candidate};
+
+    fn whole(cost: u64, gain: u64) -> Candidate {
+        Candidate {
+            cost,
+            gain,
+            divisible: false,
+            limit: None,
+        }
+    }
+
+    fn divisible(cost: u64, gain: u64) -> Candidate {
+        Candidate {
+            cost,
+            gain,
+            divisible: true,
+            limit: None,
+        }
+    }
+
+    #[test]
+    fn optimal_selection_beats_greedy_on_whole_orders() {
+        // sorted by price, the best order blocks the two that fill the budget exactly
+        let candidates = [whole(6, 12), whole(5, 9), whole(5, 9)];
+
+        let greedy = select_greedy(&candidates, 10);
+        assert_eq!(greedy.fills, vec![(0, 6)]);
+        assert_eq!(greedy.gain, 12);
+
+        let report = select_optimal(&candidates, 10, Duration::from_secs(1));
+        assert!(report.complete);
+        assert_eq!(report.greedy, greedy);
+        assert_eq!(report.optimal.cost, 10);
+        assert_eq!(report.optimal.gain, 18);
+    }
+
+    #[test]
+    fn optimal_selection_fills_leftover_budget_with_divisible_orders() {
+        let candidates = [whole(6, 12), divisible(10, 15), whole(4, 7)];
+
+        let report = select_optimal(&candidates, 12, Duration::from_secs(1));
+        assert!(report.complete);
+        // both whole orders, then 2 units of the divisible order
+        assert_eq!(report.optimal.cost, 12);
+        assert_eq!(report.optimal.gain, 12 + 7 + 3);
+        assert!(report.optimal.fills.contains(&(1, 2)));
+    }
+
+    #[test]
+    fn optimal_selection_prefers_cheaper_selection_for_same_gain() {
+        let candidates = [whole(5, 10), whole(4, 10)];
+
+        let report = select_optimal(&candidates, 5, Duration::from_secs(1));
+        assert_eq!(report.optimal.fills, vec![(1, 4)]);
+    }
+
+    #[test]
+    fn optimal_selection_does_not_depend_on_candidate_order() {
+        // two small orders and a large one reach the same gain for the same cost
+        let candidates = [whole(2, 4), whole(3, 6), whole(5, 10)];
+
+        for rotation in 0..candidates.len() {
+            let mut rotated = candidates;
+            rotated.rotate_left(rotation);
+
+            let report = select_optimal(&rotated, 5, Duration::from_secs(1));
+            assert!(report.complete);
+            assert_eq!(report.optimal.fills.len(), 1);
+            let (index, cost) = report.optimal.fills[0];
+            assert_eq!((rotated[index], cost), (whole(5, 10), 5));
+        }
+    }
+
+    #[test]
+    fn exhausted_time_budget_falls_back_to_greedy() {
+        let candidates = [whole(6, 12), whole(5, 9), whole(5, 9)];
+
+        let report = select_optimal(&candidates, 10, Duration::ZERO);
+        assert!(!report.complete);
+        assert_eq!(report.optimal, report.greedy);
+    }
+}
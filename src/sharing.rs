@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::SHARED_ORDERS_FILE_PATH,
+    utils::{load_toml, save_toml},
+};
+
+// Shared order
+// ================================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShareDirection {
+    /// The note of an own order was exported for the counterparty.
+    Sent,
+    /// The note of an order of the counterparty was imported.
+    Received,
+}
+
+/// An order note exchanged off-chain with a counterparty, typically a private order whose details
+/// are only known to the accounts it was shared with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedOrder {
+    /// Note id of the order, as hex.
+    pub note_id: String,
+    /// Account id or name of the counterparty, as given when sharing.
+    pub counterparty: String,
+    pub direction: ShareDirection,
+    /// Path of the note file written or read.
+    pub file: String,
+    /// Sync height of the client when the note was shared.
+    pub block_num: u32,
+}
+
+// Shared order book
+// ================================================================================================
+
+/// Order notes exchanged off-chain, persisted in the local shared orders file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SharedOrderBook {
+    pub shares: Vec<SharedOrder>,
+}
+
+impl SharedOrderBook {
+    pub fn load() -> Result<Self, String> {
+        load_toml(SHARED_ORDERS_FILE_PATH)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_toml(SHARED_ORDERS_FILE_PATH, self)
+    }
+
+    /// Records that the note `note_id` was exchanged with `counterparty`. Sharing the same note
+    /// again with the same counterparty only updates the file and block of the existing record.
+    pub fn record(
+        &mut self,
+        note_id: String,
+        counterparty: String,
+        direction: ShareDirection,
+        file: String,
+        block_num: u32,
+    ) {
+        match self.shares.iter_mut().find(|share| {
+            share.note_id == note_id
+                && share.counterparty == counterparty
+                && share.direction == direction
+        }) {
+            Some(share) => {
+                share.file = file;
+                share.block_num = block_num;
+            }
+            None => self.shares.push(SharedOrder {
+                note_id,
+                counterparty,
+                direction,
+                file,
+                block_num,
+            }),
+        }
+    }
+
+    /// Counterparties the note `note_id` was sent to.
+    pub fn recipients(&self, note_id: &str) -> Vec<&str> {
+        self.shares
+            .iter()
+            .filter(|share| share.direction == ShareDirection::Sent && share.note_id == note_id)
+            .map(|share| share.counterparty.as_str())
+            .collect()
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{ShareDirection, SharedOrderBook};
+
+    #[test]
+    fn shares_are_recorded_once_per_counterparty() {
+        let mut book = SharedOrderBook::default();
+        book.record(
+            "0x01".to_string(),
+            "alice".to_string(),
+            ShareDirection::Sent,
+            "0x01.mno".to_string(),
+            10,
+        );
+        book.record(
+            "0x01".to_string(),
+            "bob".to_string(),
+            ShareDirection::Sent,
+            "0x01.mno".to_string(),
+            11,
+        );
+        book.record(
+            "0x01".to_string(),
+            "alice".to_string(),
+            ShareDirection::Sent,
+            "alice.mno".to_string(),
+            12,
+        );
+        book.record(
+            "0x02".to_string(),
+            "alice".to_string(),
+            ShareDirection::Received,
+            "0x02.mno".to_string(),
+            13,
+        );
+
+        assert_eq!(book.shares.len(), 3);
+        assert_eq!(book.recipients("0x01"), vec!["alice", "bob"]);
+        assert_eq!(
+            (book.shares[0].file.as_str(), book.shares[0].block_num),
+            ("alice.mno", 12)
+        );
+
+        // received notes have no recipient
+        assert!(book.recipients("0x02").is_empty());
+    }
+}
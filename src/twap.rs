@@ -0,0 +1,310 @@
+use clap::ValueEnum;
+use miden_client::{
+    accounts::AccountId,
+    assets::{Asset, FungibleAsset},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::TWAPS_FILE_PATH,
+    order::{Fill, Order, TimeInForce},
+    price::Price,
+    utils::{load_toml, save_toml},
+};
+
+// TWAP
+// ================================================================================================
+
+/// Time between two child orders of a TWAP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TwapInterval {
+    Blocks(u32),
+    Seconds(u64),
+}
+
+/// What happens to the part of a child order that could not be filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum UnfilledChild {
+    /// Drop it, the TWAP ends up smaller than requested
+    Skip,
+    /// Add it to the next child order
+    CarryOver,
+    /// Post it to the order book at the limit price
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TwapStatus {
+    /// Child orders are still to be executed.
+    Running,
+    /// Every child order has been executed.
+    Completed,
+    /// Cancelled by its creator before every child order was executed.
+    Cancelled,
+    /// A child order could not be executed, the next ones are not executed.
+    Failed,
+}
+
+/// A parent order split into equal child orders executed at a regular interval, so a large trade
+/// does not walk the book at once. Progress is tracked locally.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Twap {
+    pub id: u32,
+    pub account_id: AccountId,
+    pub source_faucet_id: AccountId,
+    pub target_faucet_id: AccountId,
+    /// Total amount offered, with `target_amount` setting the limit price of every child order.
+    pub source_amount: u64,
+    pub target_amount: u64,
+    pub children: u32,
+    pub interval: TwapInterval,
+    pub on_unfilled: UnfilledChild,
+    /// Best amount of the target asset received per unit of the source asset when the TWAP was
+    /// added, if the book was not empty.
+    pub arrival_price: Option<Price>,
+    pub executed_children: u32,
+    /// Block number or timestamp, depending on the interval, at which the next child is due.
+    pub next_run: u64,
+    /// Source amount paid by the fills of the child orders.
+    pub spent: u64,
+    /// Target amount received by the fills of the child orders.
+    pub received: u64,
+    /// Source amount posted to the order book by unfilled child orders.
+    pub posted: u64,
+    /// Source amount dropped by unfilled child orders.
+    pub skipped: u64,
+    /// Source amount carried over to the next child order.
+    pub carried: u64,
+    pub status: TwapStatus,
+}
+
+impl Twap {
+    pub fn is_running(&self) -> bool {
+        self.status == TwapStatus::Running
+    }
+
+    /// Limit price of every child order, as amount requested per unit offered.
+    pub fn price(&self) -> Price {
+        Price::new(self.target_amount, self.source_amount)
+    }
+
+    /// Whether the next child order is due at `block_num` or `timestamp`.
+    pub fn is_due(&self, block_num: u32, timestamp: u64) -> bool {
+        let now = match self.interval {
+            TwapInterval::Blocks(_) => block_num as u64,
+            TwapInterval::Seconds(_) => timestamp,
+        };
+        self.is_running() && now >= self.next_run
+    }
+
+    /// Order of the next child, or `None` if every child has been executed. The last child takes
+    /// the rounding remainder, and every child takes what the previous one carried over.
+    pub fn next_child(&self) -> Option<Order> {
+        if self.executed_children >= self.children {
+            return None;
+        }
+
+        let child_amount = self.source_amount / self.children as u64;
+        let source_amount = if self.executed_children + 1 == self.children {
+            self.source_amount - child_amount * (self.children as u64 - 1)
+        } else {
+            child_amount
+        } + self.carried;
+        if source_amount == 0 {
+            return None;
+        }
+
+        // the requested amount is rounded up so a child never trades below the limit price
+        let target_amount = self.price().mul_ceil(source_amount)?;
+        let source_asset = FungibleAsset::new(self.source_faucet_id, source_amount).ok()?;
+        let target_asset = FungibleAsset::new(self.target_faucet_id, target_amount).ok()?;
+
+        // Children to post keep their unfilled part as a resting order
+        let time_in_force = match self.on_unfilled {
+            UnfilledChild::Post => TimeInForce::GoodTilCancelled,
+            UnfilledChild::Skip | UnfilledChild::CarryOver => TimeInForce::ImmediateOrCancel,
+        };
+
+        Some(
+            Order::new(
+                None,
+                Asset::Fungible(source_asset),
+                Asset::Fungible(target_asset),
+            )
+            .with_time_in_force(time_in_force),
+        )
+    }
+
+    /// Records the execution of `child` at `block_num` or `timestamp`, with its `fills` and the
+    /// `posted` order holding its unfilled part, and schedules the next child.
+    pub fn record_child(
+        &mut self,
+        child: &Order,
+        fills: &[Fill],
+        posted: Option<&Order>,
+        block_num: u32,
+        timestamp: u64,
+    ) {
+        let paid: u64 = fills.iter().map(Fill::target_amount).sum();
+        let posted = posted.map_or(0, |order| order.source_asset().unwrap_fungible().amount());
+        let unfilled = child.source_asset().unwrap_fungible().amount() - paid - posted;
+
+        self.spent += paid;
+        self.received += fills.iter().map(Fill::source_amount).sum::<u64>();
+        self.posted += posted;
+        self.executed_children += 1;
+
+        let is_last = self.executed_children == self.children;
+        self.carried = 0;
+        match self.on_unfilled {
+            UnfilledChild::CarryOver if !is_last => self.carried = unfilled,
+            _ => self.skipped += unfilled,
+        }
+
+        self.next_run = match self.interval {
+            TwapInterval::Blocks(blocks) => block_num as u64 + blocks as u64,
+            TwapInterval::Seconds(seconds) => timestamp + seconds,
+        };
+        if is_last {
+            self.status = TwapStatus::Completed;
+        }
+    }
+
+    /// Records that the next child order could not be executed, which stops the TWAP.
+    pub fn fail(&mut self) {
+        self.status = TwapStatus::Failed;
+    }
+
+    /// Average amount of the target asset received per unit of the source asset paid.
+    pub fn average_price(&self) -> Option<Price> {
+        (self.spent > 0).then(|| Price::new(self.received, self.spent))
+    }
+
+    /// Difference between the average execution price and the arrival price in basis points, a
+    /// negative value meaning less was received than at arrival.
+    pub fn slippage_bps(&self) -> Option<i64> {
+        self.average_price()?.bps_from(&self.arrival_price?)
+    }
+}
+
+// TWAP book
+// ================================================================================================
+
+/// TWAPs persisted in the local TWAPs file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TwapBook {
+    pub twaps: Vec<Twap>,
+}
+
+impl TwapBook {
+    pub fn load() -> Result<Self, String> {
+        load_toml(TWAPS_FILE_PATH)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_toml(TWAPS_FILE_PATH, self)
+    }
+
+    /// Adds a TWAP whose first child is due immediately, returning its local id. The arrival price
+    /// is left for the caller to record.
+    pub fn add(
+        &mut self,
+        account_id: AccountId,
+        source_asset: FungibleAsset,
+        target_asset: FungibleAsset,
+        children: u32,
+        interval: TwapInterval,
+        on_unfilled: UnfilledChild,
+    ) -> u32 {
+        let id = self.twaps.iter().map(|twap| twap.id + 1).max().unwrap_or(0);
+        self.twaps.push(Twap {
+            id,
+            account_id,
+            source_faucet_id: source_asset.faucet_id(),
+            target_faucet_id: target_asset.faucet_id(),
+            source_amount: source_asset.amount(),
+            target_amount: target_asset.amount(),
+            children,
+            interval,
+            on_unfilled,
+            arrival_price: None,
+            executed_children: 0,
+            next_run: 0,
+            spent: 0,
+            received: 0,
+            posted: 0,
+            skipped: 0,
+            carried: 0,
+            status: TwapStatus::Running,
+        });
+        id
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Twap> {
+        self.twaps.iter_mut().find(|twap| twap.id == id)
+    }
+}
+
+// Tests
+/////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use miden_client::{
+        accounts::AccountId,
+        assets::{Asset, FungibleAsset},
+    };
+
+    use super::{TwapBook, TwapInterval, TwapStatus, UnfilledChild};
+    use crate::{order::Order, price::Price};
+
+    #[test]
+    fn unfilled_children_are_carried_over() {
+        let account_id = AccountId::from_hex("0x9b5f2a3c1d6e4f70").unwrap();
+        let source_faucet_id = AccountId::from_hex("0x227bd163275aa1bf").unwrap();
+        let target_faucet_id = AccountId::from_hex("0x2540b08edc3b087d").unwrap();
+        let asset = |faucet_id, amount| FungibleAsset::new(faucet_id, amount).unwrap();
+
+        let mut book = TwapBook::default();
+        let id = book.add(
+            account_id,
+            asset(source_faucet_id, 100),
+            asset(target_faucet_id, 100),
+            3,
+            TwapInterval::Blocks(5),
+            UnfilledChild::CarryOver,
+        );
+        let twap = book.get_mut(id).unwrap();
+        twap.arrival_price = Some(Price::new(2, 1));
+        assert!(twap.is_due(0, 0));
+
+        // a resting order paying 2 target per source, for up to 20 source
+        let resting = Order::new_partial(
+            None,
+            Asset::Fungible(asset(target_faucet_id, 40)),
+            Asset::Fungible(asset(source_faucet_id, 20)),
+        );
+
+        // the first child only fills 20 out of 33, the rest is carried over
+        let child = twap.next_child().unwrap();
+        assert_eq!(child.source_asset().unwrap_fungible().amount(), 33);
+        twap.record_child(&child, &[resting.fill(20).unwrap()], None, 10, 0);
+        assert_eq!(twap.carried, 13);
+        assert!(!twap.is_due(14, 0));
+        assert!(twap.is_due(15, 0));
+
+        // the last child takes the rounding remainder, and nothing is carried past it
+        let child = twap.next_child().unwrap();
+        assert_eq!(child.source_asset().unwrap_fungible().amount(), 46);
+        twap.record_child(&child, &[], None, 15, 0);
+        let child = twap.next_child().unwrap();
+        assert_eq!(child.source_asset().unwrap_fungible().amount(), 80);
+        twap.record_child(&child, &[resting.fill(10).unwrap()], None, 20, 0);
+
+        assert_eq!(twap.status, TwapStatus::Completed);
+        assert!(twap.next_child().is_none());
+        assert_eq!((twap.spent, twap.received, twap.skipped), (30, 60, 70));
+        assert_eq!(twap.average_price(), Some(Price::new(2, 1)));
+        assert_eq!(twap.slippage_bps(), Some(0));
+    }
+}
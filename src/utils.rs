@@ -1,28 +1,46 @@
+use colored::Colorize;
 use core::panic;
+use log::warn;
 use miden_client::{
     accounts::AccountId,
     assets::{Asset, FungibleAsset},
     auth::{StoreAuthenticator, TransactionAuthenticator},
     config::{Endpoint, RpcConfig},
     crypto::{FeltRng, RpoRandomCoin},
-    notes::{NoteTag, NoteType},
+    notes::{NoteId, NoteTag, NoteType},
     rpc::{NodeRpcClient, TonicRpcClient},
     store::{
         sqlite_store::{config::SqliteStoreConfig, SqliteStore},
-        InputNoteRecord, NoteFilter, Store,
+        InputNoteRecord, NoteFilter, NoteStatus, Store,
     },
     transactions::{
         request::{TransactionRequest, TransactionRequestError},
         OutputNote,
     },
-    Client, Felt,
+    Client, Felt, Word,
 };
-use miden_lib::notes::create_swap_note;
+use miden_lib::notes::create_p2id_note;
 use rand::{seq::SliceRandom, Rng};
 use rusqlite::{Connection, Result};
-use std::rc::Rc;
-
-use crate::order::Order;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path, rc::Rc};
+
+use crate::{
+    book::{OrderBook, Side},
+    client::OrderBookClient,
+    conditional::ConditionalOrder,
+    errors::OrderError,
+    iceberg::Iceberg,
+    market::MarketSummary,
+    notes::{create_order_note, create_partial_swap_note},
+    order::{BatchAuction, Cross, Fill, MarketSweep, Order, Quote, Settlement},
+    price::Price,
+    router::Route,
+    safety::is_trusted_script,
+    selection::SelectionReport,
+    sharing::SharedOrder,
+    twap::Twap,
+};
 
 // Client Setup
 // ================================================================================================
@@ -66,10 +84,8 @@ pub fn create_swap_notes_transaction_request(
     felt_rng: &mut impl FeltRng,
 ) -> Result<TransactionRequest, TransactionRequestError> {
     // Setup note variables
-    let mut expected_future_notes = vec![];
     let mut own_output_notes = vec![];
     let note_type = NoteType::Public;
-    let aux = Felt::new(0);
 
     // Generate random distributions for offering and requesting assets
     let offering_distribution =
@@ -85,21 +101,105 @@ pub fn create_swap_notes_transaction_request(
             FungibleAsset::new(requesting_faucet, requesting_distribution[i as usize]).unwrap(),
         );
 
-        let (created_note, payback_note_details) = create_swap_note(
+        let created_note = create_partial_swap_note(
             sender,
             offered_asset,
             requested_asset,
             note_type,
-            aux,
+            None,
+            None,
             felt_rng,
         )?;
-        expected_future_notes.push(payback_note_details);
         own_output_notes.push(OutputNote::Full(created_note));
     }
 
-    TransactionRequest::new()
-        .with_expected_future_notes(expected_future_notes)
-        .with_own_output_notes(own_output_notes)
+    TransactionRequest::new().with_own_output_notes(own_output_notes)
+}
+
+/// Transaction request consuming the notes of `fills` in order, reclaiming the own orders of
+/// `reclaimed` and posting `remainder`. Partially fillable notes are told how much to fill.
+pub fn fills_transaction_request<R: FeltRng>(
+    fills: &[Fill],
+    remainder: Option<Order>,
+    reclaimed: &[Order],
+    account_id: AccountId,
+    rng: &mut R,
+) -> Result<TransactionRequest, OrderError> {
+    let input_notes = fills
+        .iter()
+        .map(|fill| {
+            let id = fill.order().id().ok_or(OrderError::MissingId)?;
+            let note_args = fill.order().is_partial().then(|| fill.note_args());
+            Ok((id, note_args))
+        })
+        .chain(
+            reclaimed
+                .iter()
+                .map(|order| order.id().map(|id| (id, None)).ok_or(OrderError::MissingId)),
+        )
+        .collect::<Result<Vec<(NoteId, Option<Word>)>, OrderError>>()?;
+
+    let mut transaction_request =
+        TransactionRequest::new().with_authenticated_input_notes(input_notes);
+    if let Some(remainder) = remainder {
+        let note = create_order_note(remainder, account_id, rng)?;
+        transaction_request = transaction_request
+            .with_own_output_notes(vec![OutputNote::Full(note)])
+            .map_err(|e| {
+                OrderError::InternalError(format!("Failed to create transaction request: {}", e))
+            })?;
+    }
+
+    Ok(transaction_request)
+}
+
+/// Transaction request settling a batch auction: the notes of `settlement` are consumed in order,
+/// then the owners of the orders are refunded in public P2ID notes.
+pub fn settlement_transaction_request<R: FeltRng>(
+    settlement: &Settlement,
+    account_id: AccountId,
+    rng: &mut R,
+) -> Result<TransactionRequest, OrderError> {
+    let transaction_request =
+        fills_transaction_request(settlement.fills(), None, &[], account_id, rng)?;
+
+    let refunds = settlement
+        .refunds()
+        .iter()
+        .map(|(owner, assets)| {
+            create_p2id_note(
+                account_id,
+                *owner,
+                assets.clone(),
+                NoteType::Public,
+                Felt::new(0),
+                rng,
+            )
+            .map(OutputNote::Full)
+            .map_err(|e| OrderError::InternalError(format!("Failed to create refund note: {}", e)))
+        })
+        .collect::<Result<Vec<OutputNote>, OrderError>>()?;
+    if refunds.is_empty() {
+        return Ok(transaction_request);
+    }
+
+    transaction_request
+        .with_own_output_notes(refunds)
+        .map_err(|e| {
+            OrderError::InternalError(format!("Failed to create transaction request: {}", e))
+        })
+}
+
+/// Consumes resting orders back into the wallet of their creator in a single transaction.
+pub async fn reclaim_orders<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAuthenticator>(
+    client: &mut Client<N, R, S, A>,
+    account_id: AccountId,
+    orders: &[Order],
+) -> Result<(), String> {
+    OrderBookClient::new(client)
+        .reclaim(account_id, orders)
+        .await
+        .map_err(|e| format!("Failed to reclaim orders: {}", e))
 }
 
 pub fn generate_random_distribution(n: usize, total: u64) -> Vec<u64> {
@@ -158,42 +258,186 @@ pub fn get_notes_by_tag<N: NodeRpcClient, R: FeltRng, S: Store, A: TransactionAu
         .collect()
 }
 
-pub fn get_assets_from_swap_note(note: &InputNoteRecord) -> (Asset, Asset) {
-    let source_asset =
-        Asset::Fungible(note.assets().iter().collect::<Vec<&Asset>>()[0].unwrap_fungible());
-    let target_faucet = AccountId::try_from(note.details().inputs()[7]).unwrap();
-    let target_amount = note.details().inputs()[4].as_int();
-    let target_asset = Asset::Fungible(FungibleAsset::new(target_faucet, target_amount).unwrap());
-    (source_asset, target_asset)
+/// Whether the note is a swap note, either standard or partially fillable.
+pub fn is_swap_note(note: &InputNoteRecord) -> bool {
+    is_trusted_script(note.details().script().hash())
+}
+
+/// Whether the note was consumed, or is being consumed by a submitted transaction.
+pub fn is_note_consumed(note: &InputNoteRecord) -> bool {
+    matches!(
+        note.status(),
+        NoteStatus::Consumed { .. } | NoteStatus::Processing { .. }
+    )
+}
+
+/// Orders of the swap notes among `notes`. Notes that are not valid swap notes are skipped and
+/// reported, since anyone can publish a note under the tag of a market.
+pub fn orders_from_notes(notes: impl IntoIterator<Item = InputNoteRecord>) -> Vec<Order> {
+    notes
+        .into_iter()
+        .filter_map(|note| match Order::try_from(&note) {
+            Ok(order) => Some(order),
+            Err(err) => {
+                warn!("Skipping note {}: {}", note.id(), err);
+                None
+            }
+        })
+        .collect()
 }
 
 pub fn print_order_table(title: &str, orders: &[Order]) {
     let mut table = Vec::new();
-    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+".to_string());
-    table.push("| Note ID                                                            | Requested Asset    | Amount Requested | Offered Asset      | Offered Amount   | Price    |".to_string());
-    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+".to_string());
+    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+------------+".to_string());
+    table.push("| Note ID                                                            | Requested Asset    | Amount Requested | Offered Asset      | Offered Amount   | Price    | Expires    |".to_string());
+    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+------------+".to_string());
 
     for order in orders {
         let note_id = order
             .id()
             .map_or_else(|| "N/A".to_string(), |id| id.to_string());
         let source_asset_faucet_id = order.source_asset().faucet_id().to_string();
-        let source_asset_amount = order.source_asset().unwrap_fungible().amount();
+        // Non-fungible assets are listed as a single unit
+        let source_asset_amount = order.source_amount();
         let target_asset_faucet_id = order.target_asset().faucet_id().to_string();
-        let target_asset_amount = order.target_asset().unwrap_fungible().amount();
+        let target_asset_amount = order.target_amount();
+        let expiration = order
+            .expiration()
+            .map_or_else(|| "Never".to_string(), |block| block.to_string());
 
         table.push(format!(
-            "| {:<66} | {:<16} | {:<16} | {:<16} | {:<16} | {:<8.2} |",
+            "| {:<66} | {:<16} | {:<16} | {:<16} | {:<16} | {:<8.2} | {:<10} |",
             note_id,
             target_asset_faucet_id,
             target_asset_amount,
             source_asset_faucet_id,
             source_asset_amount,
-            order.price()
+            order.price(),
+            expiration
+        ));
+    }
+
+    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+------------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+}
+
+/// Prints both sides of `book` aggregated by price level, at most `max_levels` per side, with
+/// the cumulative size from the best price, the spread and the mid price.
+pub fn print_depth(title: &str, book: &OrderBook, tick: Option<Price>, max_levels: usize) {
+    let bids = book.depth(Side::Bid, tick);
+    let asks = book.depth(Side::Ask, tick);
+
+    let mut table = Vec::new();
+    table.push("+-------+------------------+------------------+--------------++--------------+------------------+------------------+-------+".to_string());
+    table.push("| Notes | Bid Total        | Bid Size         | Bid Price    || Ask Price    | Ask Size         | Ask Total        | Notes |".to_string());
+    table.push("+-------+------------------+------------------+--------------++--------------+------------------+------------------+-------+".to_string());
+
+    let (mut bid_total, mut ask_total) = (0u64, 0u64);
+    for index in 0..bids.len().max(asks.len()).min(max_levels) {
+        let bid = match bids.get(index) {
+            Some(level) => {
+                bid_total += level.size;
+                let price = format!("{:>12.6}", level.price);
+                format!(
+                    "| {:>5} | {:>16} | {:>16} | {}",
+                    level.orders,
+                    bid_total,
+                    level.size,
+                    if index == 0 {
+                        price.green().bold()
+                    } else {
+                        price.normal()
+                    }
+                )
+            }
+            None => format!("| {:>5} | {:>16} | {:>16} | {:>12}", "", "", "", ""),
+        };
+        let ask = match asks.get(index) {
+            Some(level) => {
+                ask_total += level.size;
+                let price = format!("{:<12.6}", level.price);
+                format!(
+                    "{} | {:<16} | {:<16} | {:<5} |",
+                    if index == 0 {
+                        price.red().bold()
+                    } else {
+                        price.normal()
+                    },
+                    level.size,
+                    ask_total,
+                    level.orders
+                )
+            }
+            None => format!("{:<12} | {:<16} | {:<16} | {:<5} |", "", "", "", ""),
+        };
+        table.push(format!("{} || {}", bid, ask));
+    }
+
+    table.push("+-------+------------------+------------------+--------------++--------------+------------------+------------------+-------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+
+    let summary = match (book.spread(), book.mid_price()) {
+        (Some(spread), Some(mid_price)) => format!(
+            "Spread: {:.6} ({} bps)    Mid price: {:.6}",
+            spread,
+            book.spread_bps()
+                .map_or("N/A".to_string(), |bps| bps.to_string()),
+            mid_price
+        ),
+        (None, Some(mid_price)) => format!("Book is crossed    Mid price: {:.6}", mid_price),
+        _ => "Spread: N/A, one side of the book is empty".to_string(),
+    };
+    println!("{}", summary.bold());
+}
+
+/// Prints one row per market with its top of book, resting liquidity on each side and the volume
+/// traded since the block of the summary.
+pub fn print_ticker(title: &str, summaries: &[MarketSummary]) {
+    let price = |price: Option<Price>| price.map_or("N/A".to_string(), |p| format!("{:.6}", p));
+
+    let mut table = Vec::new();
+    table.push("+---------------------------------------+--------------+--------------+------------+--------------+-----------+------------------+-----------+------------------+------------------+------------------+--------+".to_string());
+    table.push("| Market                                | Best Bid     | Best Ask     | Spread bps | Mid Price    | Bid Notes | Bid Liquidity    | Ask Notes | Ask Liquidity    | Base Volume      | Quote Volume     | Trades |".to_string());
+    table.push("+---------------------------------------+--------------+--------------+------------+--------------+-----------+------------------+-----------+------------------+------------------+------------------+--------+".to_string());
+
+    for summary in summaries {
+        let market = format!("{}/{}", summary.base_faucet_id, summary.quote_faucet_id);
+        let spread_bps = summary
+            .spread_bps
+            .map_or("N/A".to_string(), |bps| bps.to_string());
+
+        table.push(format!(
+            "| {:<37} | {} | {} | {:>10} | {:>12} | {:>9} | {:>16} | {:>9} | {:>16} | {:>16} | {:>16} | {:>6} |",
+            market,
+            format!("{:>12}", price(summary.best_bid)).green(),
+            format!("{:>12}", price(summary.best_ask)).red(),
+            spread_bps,
+            price(summary.mid_price),
+            summary.bid_notes,
+            summary.bid_liquidity,
+            summary.ask_notes,
+            summary.ask_liquidity,
+            summary.volume.base_amount,
+            summary.volume.quote_amount,
+            summary.volume.trades
         ));
     }
 
-    table.push("+--------------------------------------------------------------------+--------------------+------------------+--------------------+------------------+----------+".to_string());
+    table.push("+---------------------------------------+--------------+--------------+------------+--------------+-----------+------------------+-----------+------------------+------------------+------------------+--------+".to_string());
 
     // Print title
     println!("{}\n", title);
@@ -204,20 +448,20 @@ pub fn print_order_table(title: &str, orders: &[Order]) {
     }
 }
 
-pub fn print_balance_update(orders: &[Order]) {
-    if orders.is_empty() {
+pub fn print_balance_update(fills: &[Fill]) {
+    if fills.is_empty() {
         println!("No orders to process. Your balance will not change.");
         return;
     }
 
     let mut total_source_asset = 0u64;
     let mut total_target_asset = 0u64;
-    let source_faucet_id = orders[0].target_asset().faucet_id();
-    let target_faucet_id = orders[0].source_asset().faucet_id();
+    let source_faucet_id = fills[0].order().target_asset().faucet_id();
+    let target_faucet_id = fills[0].order().source_asset().faucet_id();
 
-    for order in orders {
-        total_source_asset += order.target_asset().unwrap_fungible().amount();
-        total_target_asset += order.source_asset().unwrap_fungible().amount();
+    for fill in fills {
+        total_source_asset += fill.target_amount();
+        total_target_asset += fill.source_amount();
     }
 
     println!("Balance Update Preview:");
@@ -228,7 +472,294 @@ pub fn print_balance_update(orders: &[Order]) {
     println!("\nAssets you will spend:");
     println!("  Faucet ID: {}", source_faucet_id);
     println!("  Amount: {}", total_source_asset);
+    println!(
+        "\nAverage price: {}",
+        Price::new(total_source_asset, total_target_asset)
+    );
+    println!("------------------------");
+}
+
+pub fn print_quote(quote: &Quote, execution_error: Option<&str>) {
+    let orders: Vec<Order> = quote.fills().iter().map(Fill::order).collect();
+    print_order_table("Selected notes:", &orders);
+    print_balance_update(quote.fills());
+
+    let price_or_na = |price: Option<Price>| price.map_or("N/A".to_string(), |p| p.to_string());
+    println!("Quote:");
+    println!("------------------------");
+    println!("  Best price: {}", price_or_na(quote.best_price()));
+    println!("  Average price: {}", price_or_na(quote.average_price()));
+    println!("  Worst price: {}", price_or_na(quote.worst_price()));
+    println!(
+        "  Price impact: {}",
+        quote
+            .price_impact_bps()
+            .map_or("N/A".to_string(), |bps| format!("{} bps", bps))
+    );
+    println!("  Leftover source amount: {}", quote.leftover());
+    match execution_error {
+        None => println!("  Local execution: succeeded, nothing was submitted"),
+        Some(err) => println!("  Local execution: failed, {}", err),
+    }
+    println!("------------------------");
+}
+
+pub fn print_selection_report(report: &SelectionReport) {
+    println!("Order Selection Report:");
+    println!("------------------------");
+    for (name, selection) in [("Greedy", &report.greedy), ("Optimal", &report.optimal)] {
+        println!("{}:", name);
+        println!("  Notes: {}", selection.fills.len());
+        println!("  Spend: {}", selection.cost);
+        println!("  Receive: {}", selection.gain);
+        println!(
+            "  Average price: {}",
+            Price::new(selection.cost, selection.gain)
+        );
+    }
+    if !report.complete {
+        println!("\nTime budget exhausted, showing the best selection found.");
+    }
+    println!("------------------------");
+}
+
+pub fn print_market_sweep(sweep: &MarketSweep, max_slippage_bps: u32) {
+    println!("Market Order Preview:");
+    println!("------------------------");
+    println!("  Best price: {}", sweep.best_price());
+    println!("  Average price: {}", sweep.average_price());
+    println!("  Worst price: {}", sweep.worst_price());
+    println!("  Slippage bound: {} bps", max_slippage_bps);
+    println!("------------------------");
+}
+
+pub fn print_route(route: &Route) {
+    println!("Route Preview:");
+    println!("------------------------");
+    for (index, leg) in route.legs().iter().enumerate() {
+        println!("Leg {}:", index + 1);
+        println!("  Pay: {} of {}", leg.amount_in(), leg.source_faucet_id());
+        println!(
+            "  Receive: {} of {}",
+            leg.amount_out(),
+            leg.target_faucet_id()
+        );
+        println!("  Notes: {}", leg.fills().len());
+        println!("  Average price: {}", leg.price());
+    }
+    println!("\nTotal:");
+    println!("  Spend: {}", route.amount_in());
+    println!("  Receive: {}", route.amount_out());
+    println!("  Average price: {}", route.price());
+    println!("------------------------");
+}
+
+pub fn print_batch_auction(auction: &BatchAuction) {
+    println!("Batch Auction:");
+    println!("------------------------");
+    println!("  Clearing price: {}", auction.clearing_price());
+    println!("  Volume: {}", auction.volume());
+    println!("  Rounding surplus: {}", auction.surplus());
     println!("------------------------");
+
+    let orders: Vec<Order> = auction.fills().iter().map(|fill| fill.order()).collect();
+    print_order_table("Orders cleared:", &orders);
+
+    println!("\nSettlement:");
+    for fill in auction.fills() {
+        let order = fill.order();
+        println!(
+            "  {}: gives {} of {}, receives {} of {}",
+            order
+                .id()
+                .map_or_else(|| "N/A".to_string(), |id| id.to_string()),
+            fill.source_amount(),
+            order.source_asset().faucet_id(),
+            fill.target_amount(),
+            order.target_asset().faucet_id()
+        );
+    }
+}
+
+pub fn print_crosses(crosses: &[Cross]) {
+    for (index, cross) in crosses.iter().enumerate() {
+        let orders: Vec<Order> = cross.fills().iter().map(Fill::order).collect();
+        print_order_table(format!("Crossing orders {}:", index + 1).as_str(), &orders);
+    }
+
+    println!("\nSpread Preview:");
+    println!("------------------------");
+    let mut spread: Vec<(AccountId, u64)> = Vec::new();
+    for (faucet_id, amount) in crosses.iter().flat_map(Cross::spread) {
+        match spread.iter_mut().find(|(id, _)| *id == faucet_id) {
+            Some((_, total)) => *total += amount,
+            None => spread.push((faucet_id, amount)),
+        }
+    }
+    for (faucet_id, amount) in spread {
+        println!("  Faucet ID: {}", faucet_id);
+        println!("  Amount: {}", amount);
+    }
+    println!("------------------------");
+}
+
+pub fn print_icebergs(title: &str, icebergs: &[Iceberg]) {
+    let mut table = Vec::new();
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------------------------------------------------------------+-----------+".to_string());
+    table.push("| ID   | Offered Asset      | Requested Asset    | Price    | Visible Amount   | Hidden Amount    | Resting Note ID                                                    | Status    |".to_string());
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------------------------------------------------------------+-----------+".to_string());
+
+    for iceberg in icebergs {
+        table.push(format!(
+            "| {:<4} | {:<18} | {:<18} | {:<8.2} | {:<16} | {:<16} | {:<66} | {:<9} |",
+            iceberg.id,
+            iceberg.source_faucet_id.to_string(),
+            iceberg.target_faucet_id.to_string(),
+            iceberg.price(),
+            iceberg.visible_amount,
+            iceberg.hidden_amount,
+            iceberg.note_id.as_deref().unwrap_or("N/A"),
+            format!("{:?}", iceberg.status)
+        ));
+    }
+
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------------------------------------------------------------+-----------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+}
+
+pub fn print_conditional_orders(title: &str, orders: &[ConditionalOrder]) {
+    let mut table = Vec::new();
+    table.push("+------+--------------------+--------------------+------------------+------------------+-------------+--------------+-----------+-----------+--------------+".to_string());
+    table.push("| ID   | Offered Asset      | Requested Asset    | Offered Amount   | Requested Amount | Kind        | Trigger      | TIF       | Status    | Triggered At |".to_string());
+    table.push("+------+--------------------+--------------------+------------------+------------------+-------------+--------------+-----------+-----------+--------------+".to_string());
+
+    for order in orders {
+        table.push(format!(
+            "| {:<4} | {:<18} | {:<18} | {:<16} | {:<16} | {:<11} | {:<12.4} | {:<9} | {:<9} | {:<12} |",
+            order.id,
+            order.source_faucet_id.to_string(),
+            order.target_faucet_id.to_string(),
+            order.source_amount,
+            order.target_amount,
+            format!("{:?}", order.kind),
+            order.trigger_price,
+            format!("{:?}", order.time_in_force),
+            format!("{:?}", order.status),
+            order
+                .triggered_at
+                .map_or("N/A".to_string(), |block| block.to_string())
+        ));
+    }
+
+    table.push("+------+--------------------+--------------------+------------------+------------------+-------------+--------------+-----------+-----------+--------------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+}
+
+pub fn print_twaps(title: &str, twaps: &[Twap]) {
+    let mut table = Vec::new();
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------+---------------+----------------+-----------+".to_string());
+    table.push("| ID   | Offered Asset      | Requested Asset    | Children | Spent            | Received         | Avg Price    | Arrival Price | Slippage (bps) | Status    |".to_string());
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------+---------------+----------------+-----------+".to_string());
+
+    let price_or_na =
+        |price: Option<Price>| price.map_or("N/A".to_string(), |p| format!("{:.4}", p));
+    for twap in twaps {
+        table.push(format!(
+            "| {:<4} | {:<18} | {:<18} | {:<8} | {:<16} | {:<16} | {:<12} | {:<13} | {:<14} | {:<9} |",
+            twap.id,
+            twap.source_faucet_id.to_string(),
+            twap.target_faucet_id.to_string(),
+            format!("{}/{}", twap.executed_children, twap.children),
+            twap.spent,
+            twap.received,
+            price_or_na(twap.average_price()),
+            price_or_na(twap.arrival_price),
+            twap.slippage_bps()
+                .map_or("N/A".to_string(), |bps| bps.to_string()),
+            format!("{:?}", twap.status)
+        ));
+    }
+
+    table.push("+------+--------------------+--------------------+----------+------------------+------------------+--------------+---------------+----------------+-----------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+
+    let posted: u64 = twaps.iter().map(|twap| twap.posted).sum();
+    let skipped: u64 = twaps.iter().map(|twap| twap.skipped).sum();
+    if posted > 0 || skipped > 0 {
+        println!(
+            "\nUnfilled amount posted to the order book: {}, skipped: {}",
+            posted, skipped
+        );
+    }
+}
+
+pub fn print_shared_orders(title: &str, shares: &[SharedOrder]) {
+    let mut table = Vec::new();
+    table.push("+--------------------------------------------------------------------+----------------------+-----------+----------+--------------------------------+".to_string());
+    table.push("| Note ID                                                            | Counterparty         | Direction | Block    | File                           |".to_string());
+    table.push("+--------------------------------------------------------------------+----------------------+-----------+----------+--------------------------------+".to_string());
+
+    for share in shares {
+        table.push(format!(
+            "| {:<66} | {:<20} | {:<9} | {:<8} | {:<30} |",
+            share.note_id,
+            share.counterparty,
+            format!("{:?}", share.direction),
+            share.block_num,
+            share.file
+        ));
+    }
+
+    table.push("+--------------------------------------------------------------------+----------------------+-----------+----------+--------------------------------+".to_string());
+
+    // Print title
+    println!("{}\n", title);
+
+    // Print table
+    for line in table {
+        println!("{}", line);
+    }
+}
+
+// Local State
+// ================================================================================================
+
+/// Reads state persisted as TOML in `path`, defaulting to an empty state if the file is missing.
+pub fn load_toml<T: DeserializeOwned + Default>(path: &str) -> Result<T, String> {
+    if !Path::new(path).exists() {
+        return Ok(T::default());
+    }
+
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))
+}
+
+pub fn save_toml<T: Serialize>(path: &str, state: &T) -> Result<(), String> {
+    let contents =
+        toml::to_string(state).map_err(|e| format!("Failed to serialize {}: {}", path, e))?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path, e))
 }
 
 pub fn clear_notes_tables(db_path: &str) -> Result<()> {